@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+
+use axum_keycloak_auth::decode::{Access, LazyRoles, RealmAccess, ResourceAccess};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+
+/// Roles of a token carrying 500 realm roles and 100 roles of each of 5 clients.
+fn roles() -> LazyRoles<String> {
+    LazyRoles::new(
+        Some(RealmAccess(Access {
+            roles: (0..500).map(|i| format!("realm-role-{i}")).collect(),
+        })),
+        Some(ResourceAccess(
+            (0..5)
+                .map(|client| {
+                    (
+                        format!("client-{client}"),
+                        Access {
+                            roles: (0..100).map(|i| format!("client-role-{i}")).collect(),
+                        },
+                    )
+                })
+                .collect::<HashMap<_, _>>(),
+        )),
+    )
+}
+
+/// 20 checks per request, half of them for absent roles.
+fn checked() -> Vec<String> {
+    (0..10)
+        .map(|i| format!("realm-role-{}", i * 50))
+        .chain((0..10).map(|i| format!("missing-role-{i}")))
+        .collect()
+}
+
+fn role_checks(c: &mut Criterion) {
+    let checked = checked();
+    let mut group = c.benchmark_group("20 role checks on 1000 roles");
+
+    group.bench_function("scanning", |b| {
+        b.iter_batched(
+            roles,
+            |roles| {
+                for role in &checked {
+                    black_box(roles.iter().any(|it| it.role() == role));
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("memoized", |b| {
+        b.iter_batched(
+            roles,
+            |roles| {
+                for role in &checked {
+                    black_box(roles.contains(role));
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, role_checks);
+criterion_main!(benches);
@@ -0,0 +1,241 @@
+use std::{
+    fmt::Debug,
+    marker::PhantomData,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use axum::extract::MatchedPath;
+use futures::future::BoxFuture;
+use http::{Method, Request, Response, StatusCode};
+use tower::{Layer, Service};
+
+use crate::{extract::token_from_extensions, role::Role};
+
+/// One handled request, as recorded by the `AccessLogLayer`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessLogEntry {
+    pub method: Method,
+    /// The matched route (e.g. "/users/:id") if the request was routed by an axum `Router`, its path otherwise.
+    pub route: String,
+    /// Subject of the token. `None` if the request was not authenticated (only possible in `PassthroughMode::Pass`).
+    pub subject: Option<String>,
+    /// Authorized party (client) of the token.
+    pub client: Option<String>,
+    /// Number of roles held by the token.
+    pub roles: Option<usize>,
+    /// Status of the response. `None` if the inner service failed.
+    pub status: Option<StatusCode>,
+    pub latency: Duration,
+}
+
+/// Receives an `AccessLogEntry` for every request handled by an `AccessLogLayer`.
+///
+/// Entries are delivered synchronously on the request path. Implementations should therefore return quickly.
+pub trait AccessLogSink: Debug + Send + Sync {
+    fn on_entry(&self, entry: &AccessLogEntry);
+}
+
+/// Records an auth-aware access log: An `AccessLogEntry` per request, naming the subject and client of the request's token,
+/// the number of roles it holds, the route, the response status and the latency.
+///
+/// Entries are emitted as tracing events (target "axum_keycloak_auth::access_log", level INFO)
+/// and, if configured, forwarded to an `AccessLogSink`.
+///
+/// Must be placed after (inside) a `KeycloakAuthLayer<R>`, as the token is read from the request's extensions.
+/// When added to a router, add this layer before the `KeycloakAuthLayer`.
+/// In `PassthroughMode::Block`, rejected requests never reach this layer.
+pub struct AccessLogLayer<R: Role = String> {
+    sink: Option<Arc<dyn AccessLogSink>>,
+    phantom: PhantomData<fn() -> R>,
+}
+
+impl<R: Role> AccessLogLayer<R> {
+    pub fn new() -> Self {
+        Self {
+            sink: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Additionally forwards all entries to `sink`.
+    pub fn with_sink(mut self, sink: Arc<dyn AccessLogSink>) -> Self {
+        self.sink = Some(sink);
+        self
+    }
+}
+
+impl<R: Role> Default for AccessLogLayer<R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R: Role> Clone for AccessLogLayer<R> {
+    fn clone(&self) -> Self {
+        Self {
+            sink: self.sink.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<R: Role> Debug for AccessLogLayer<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AccessLogLayer")
+            .field("sink", &self.sink)
+            .finish()
+    }
+}
+
+impl<S, R: Role> Layer<S> for AccessLogLayer<R> {
+    type Service = AccessLogMiddleware<S, R>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AccessLogMiddleware {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AccessLogMiddleware<S, R: Role> {
+    inner: S,
+    layer: AccessLogLayer<R>,
+}
+
+impl<S, R: Role + 'static, ReqBody, ResBody> Service<Request<ReqBody>> for AccessLogMiddleware<S, R>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        let started = Instant::now();
+        let token = token_from_extensions::<R>(request.extensions()).ok();
+        let mut entry = AccessLogEntry {
+            method: request.method().clone(),
+            route: match request.extensions().get::<MatchedPath>() {
+                Some(matched_path) => matched_path.as_str().to_owned(),
+                None => request.uri().path().to_owned(),
+            },
+            subject: token.map(|token| token.subject.clone()),
+            client: token.map(|token| token.authorized_party.clone()),
+            roles: token.map(|token| token.num_roles()),
+            status: None,
+            latency: Duration::ZERO,
+        };
+        let response = self.inner.call(request);
+        let sink = self.layer.sink.clone();
+
+        Box::pin(async move {
+            let response = response.await;
+            entry.status = response.as_ref().ok().map(Response::status);
+            entry.latency = started.elapsed();
+            emit(sink.as_deref(), &entry);
+            response
+        })
+    }
+}
+
+fn emit(sink: Option<&dyn AccessLogSink>, entry: &AccessLogEntry) {
+    tracing::info!(
+        target: "axum_keycloak_auth::access_log",
+        method = %entry.method,
+        route = %entry.route,
+        subject = entry.subject.as_deref(),
+        client = entry.client.as_deref(),
+        roles = entry.roles,
+        status = entry.status.map(|status| status.as_u16()),
+        latency_ms = entry.latency.as_secs_f64() * 1000.0,
+        "Handled request"
+    );
+    if let Some(sink) = sink {
+        sink.on_entry(entry);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex, PoisonError};
+
+    use axum::{body::Body, http::StatusCode, routing::get, Router};
+    use http::{Method, Request};
+    use tower::ServiceExt;
+
+    use crate::{
+        service::test::{claims, create_token, test_layer},
+        PassthroughMode,
+    };
+
+    use super::{AccessLogEntry, AccessLogLayer, AccessLogSink};
+
+    #[derive(Debug, Default)]
+    struct Entries(Mutex<Vec<AccessLogEntry>>);
+
+    impl AccessLogSink for Entries {
+        fn on_entry(&self, entry: &AccessLogEntry) {
+            self.0
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(entry.clone());
+        }
+    }
+
+    #[tokio::test]
+    async fn records_authenticated_and_unauthenticated_requests() {
+        let entries = Arc::new(Entries::default());
+        let router = Router::new()
+            .route("/users/:id", get(|| async { StatusCode::NO_CONTENT }))
+            .layer(AccessLogLayer::<String>::new().with_sink(entries.clone()))
+            .layer(
+                test_layer!()
+                    .passthrough_mode(PassthroughMode::Pass)
+                    .build(),
+            );
+
+        for authorization in [format!("Bearer {}", create_token(claims())), String::new()] {
+            let response = router
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .uri("/users/42")
+                        .header("Authorization", authorization)
+                        .body(Body::empty())
+                        .expect("valid request"),
+                )
+                .await
+                .expect("infallible");
+            assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        }
+
+        let entries = entries.0.lock().unwrap_or_else(PoisonError::into_inner);
+        let [authenticated, unauthenticated] = entries.as_slice() else {
+            panic!("expected two entries, got {entries:?}");
+        };
+        assert_eq!(authenticated.method, Method::GET);
+        assert_eq!(authenticated.route, "/users/:id");
+        assert_eq!(
+            authenticated.subject.as_deref(),
+            Some("f6c4fd0e-4d0f-4a1e-8b3c-2b6f1c8e9d7a")
+        );
+        assert_eq!(authenticated.client.as_deref(), Some("frontend"));
+        assert!(authenticated.roles.is_some_and(|roles| roles > 0));
+        assert_eq!(authenticated.status, Some(StatusCode::NO_CONTENT));
+
+        assert_eq!(unauthenticated.route, "/users/:id");
+        assert_eq!(unauthenticated.subject, None);
+        assert_eq!(unauthenticated.roles, None);
+        assert_eq!(unauthenticated.status, Some(StatusCode::NO_CONTENT));
+    }
+}
@@ -0,0 +1,73 @@
+use crate::decode::RawClaims;
+
+/// Maps legacy or broker-specific claim names to the claims this crate expects,
+/// so that tokens carrying e.g. a 'upn' instead of a 'preferred_username' claim still parse into `StandardClaims`.
+/// Commonly needed for tokens of users brokered through ADFS or Azure AD, depending on the mappers of the identity provider.
+///
+/// An alias is only used if the claim it stands for is absent (or `null`), so that tokens already carrying the claim are unaffected.
+/// If several aliases of the same claim are present, the first one added is used. The aliases themselves are kept.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClaimAliases(Vec<(String, String)>);
+
+impl ClaimAliases {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The aliases used by ADFS and Azure AD: 'upn' for 'preferred_username' and 'unique_name' for 'name'.
+    pub fn brokered() -> Self {
+        Self::new()
+            .alias("upn", "preferred_username")
+            .alias("unique_name", "name")
+    }
+
+    /// Lets the `alias` claim stand in for `claim`.
+    pub fn alias(mut self, alias: impl Into<String>, claim: impl Into<String>) -> Self {
+        self.0.push((alias.into(), claim.into()));
+        self
+    }
+
+    /// Copies the value of each present alias to the claim it stands for, unless that claim is present already.
+    pub fn apply(&self, raw_claims: &mut RawClaims) {
+        for (alias, claim) in &self.0 {
+            if raw_claims.get(claim).is_some_and(|value| !value.is_null()) {
+                continue;
+            }
+            if let Some(value) = raw_claims.get(alias).filter(|value| !value.is_null()) {
+                let value = value.clone();
+                raw_claims.insert(claim.clone(), value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use crate::{
+        decode::{RawClaims, StandardClaims},
+        service::test::claims,
+    };
+
+    use super::ClaimAliases;
+
+    #[test]
+    fn fills_absent_claims_from_aliases() {
+        let mut claims = claims();
+        let claims_object = claims.as_object_mut().expect("object");
+        claims_object.remove("preferred_username");
+        claims_object.insert(String::from("upn"), json!("jane@corp.example.com"));
+        claims_object.insert(String::from("unique_name"), json!("CORP\\jane"));
+        let raw_claims = RawClaims::deserialize(claims).expect("valid claims");
+        assert!(StandardClaims::parse(raw_claims.clone()).is_err());
+
+        let mut aliased = raw_claims;
+        ClaimAliases::brokered().apply(&mut aliased);
+        let standard_claims = StandardClaims::parse(aliased).expect("parsable standard claims");
+        assert_eq!(standard_claims.preferred_username, "jane@corp.example.com");
+        // The present 'name' claim takes precedence over its alias.
+        assert_eq!(standard_claims.name, "John Doe");
+    }
+}
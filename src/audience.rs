@@ -0,0 +1,152 @@
+use std::{borrow::Borrow, fmt::Display};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{decode::StringOrVecString, error::AuthError};
+
+/// A value of the JWT 'aud' claim which a token is expected to carry.
+///
+/// Audiences are compared exactly (case-sensitive, no trimming). An empty audience never matches any token,
+/// which is why `Audience::try_new`, the `TryFrom` implementations and deserialization all reject it.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Audience(String);
+
+impl Audience {
+    /// Creates an audience, failing with `AuthError::EmptyAudience` if `value` is empty or only consists of whitespace.
+    pub fn try_new(value: impl Into<String>) -> Result<Self, AuthError> {
+        let value = value.into();
+        match value.trim().is_empty() {
+            true => Err(AuthError::EmptyAudience),
+            false => Ok(Self(value)),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether `audiences` (e.g. the 'aud' claim of a token) contain this audience.
+    pub fn is_contained_in(&self, audiences: &StringOrVecString) -> bool {
+        audiences.contains(&self.0)
+    }
+}
+
+impl TryFrom<&str> for Audience {
+    type Error = AuthError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::try_new(value)
+    }
+}
+
+impl TryFrom<String> for Audience {
+    type Error = AuthError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::try_new(value)
+    }
+}
+
+/// Converts the expected `audiences` of a layer or validator.
+///
+/// # Panics
+///
+/// If any of the `audiences` is invalid, i.e. empty. As audiences are configured once at startup,
+/// this surfaces the misconfiguration immediately instead of rejecting every token.
+pub(crate) fn expect_audiences<A>(audiences: impl IntoIterator<Item = A>) -> Vec<Audience>
+where
+    A: TryInto<Audience>,
+    A::Error: Display,
+{
+    audiences
+        .into_iter()
+        .map(|audience| {
+            audience
+                .try_into()
+                .unwrap_or_else(|err| panic!("Invalid expected audience: {err}"))
+        })
+        .collect()
+}
+
+impl From<Audience> for String {
+    fn from(value: Audience) -> Self {
+        value.0
+    }
+}
+
+impl AsRef<str> for Audience {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Audience {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for Audience {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl PartialEq<str> for Audience {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Audience {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{decode::StringOrVecString, error::AuthError};
+
+    use super::Audience;
+
+    #[test]
+    fn validates_and_compares_audiences() {
+        assert!(matches!(
+            Audience::try_new(""),
+            Err(AuthError::EmptyAudience)
+        ));
+        assert!(matches!(
+            Audience::try_new("  "),
+            Err(AuthError::EmptyAudience)
+        ));
+        let audience = Audience::try_new("account").expect("non-empty");
+        assert_eq!(audience, "account");
+        assert_eq!(Audience::try_from("account").expect("non-empty"), audience);
+        assert_eq!(
+            Audience::try_from(String::from("account")).expect("non-empty"),
+            audience
+        );
+        assert!(matches!(
+            Audience::try_from(""),
+            Err(AuthError::EmptyAudience)
+        ));
+
+        assert!(audience.is_contained_in(&StringOrVecString::from(String::from("account"))));
+        assert!(audience.is_contained_in(&StringOrVecString::from(vec![
+            String::from("other"),
+            String::from("account"),
+        ])));
+        assert!(!audience.is_contained_in(&StringOrVecString::from(String::from("Account"))));
+    }
+
+    #[test]
+    fn rejects_empty_audiences_when_deserializing() {
+        assert_eq!(
+            serde_json::from_str::<Vec<Audience>>(r#"["account"]"#).expect("valid audiences"),
+            vec![Audience::try_new("account").expect("non-empty")]
+        );
+        assert!(serde_json::from_str::<Vec<Audience>>(r#"["account", " "]"#).is_err());
+    }
+}
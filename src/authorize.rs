@@ -0,0 +1,201 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    decode::KeycloakToken,
+    role::{Role, RoleRequirement},
+};
+
+/// A requirement combining OAuth scopes, roles and groups with and/or semantics,
+/// checked in one call using `KeycloakToken::authorize`.
+///
+/// ```rust
+/// use axum_keycloak_auth::authorize::Requirement;
+///
+/// // Either the "orders:write" scope together with the "clerk" role, or membership in the "/admins" group.
+/// let requirement = Requirement::scope("orders:write")
+///     .and(Requirement::role("clerk"))
+///     .or(Requirement::group("/admins"));
+/// assert_eq!(requirement.to_string(), "any(all(scope(orders:write), role(clerk)), group(/admins))");
+/// ```
+///
+/// Like `RoleRequirement`, requirements are (de)serializable, e.g. from `{ "all": [{ "scope": "orders:write" }, { "role": { "role": "clerk" } }] }`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Requirement {
+    /// The OAuth scope must have been granted to the token.
+    Scope(String),
+    /// The roles of the token must satisfy the role requirement.
+    Role(RoleRequirement),
+    /// The user must be a member of the group, e.g. "/engineering/backend".
+    Group(String),
+    /// All of the requirements must be satisfied.
+    All(Vec<Requirement>),
+    /// At least one of the requirements must be satisfied.
+    Any(Vec<Requirement>),
+}
+
+impl Requirement {
+    /// Requires all of the given `scopes`, `roles` and `groups`.
+    pub fn new(
+        scopes: impl IntoIterator<Item = impl Into<String>>,
+        roles: impl IntoIterator<Item = impl Into<String>>,
+        groups: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        Requirement::All(
+            scopes
+                .into_iter()
+                .map(Requirement::scope)
+                .chain(roles.into_iter().map(Requirement::role))
+                .chain(groups.into_iter().map(Requirement::group))
+                .collect(),
+        )
+    }
+
+    pub fn scope(scope: impl Into<String>) -> Self {
+        Requirement::Scope(scope.into())
+    }
+
+    /// Requires the role, as a realm role or as a client role of any client.
+    /// Use `Requirement::Role` (or `From<RoleRequirement>`) for more specific role requirements.
+    pub fn role(role: impl Into<String>) -> Self {
+        Requirement::Role(RoleRequirement::Role(role.into()))
+    }
+
+    pub fn group(group: impl Into<String>) -> Self {
+        Requirement::Group(group.into())
+    }
+
+    /// Requires this and the `other` requirement.
+    pub fn and(self, other: Requirement) -> Self {
+        match self {
+            Requirement::All(mut requirements) => {
+                requirements.push(other);
+                Requirement::All(requirements)
+            }
+            requirement => Requirement::All(vec![requirement, other]),
+        }
+    }
+
+    /// Requires this or the `other` requirement.
+    pub fn or(self, other: Requirement) -> Self {
+        match self {
+            Requirement::Any(mut requirements) => {
+                requirements.push(other);
+                Requirement::Any(requirements)
+            }
+            requirement => Requirement::Any(vec![requirement, other]),
+        }
+    }
+
+    /// The first requirement (this one or a nested one) not satisfied by the `token`.
+    /// For `Requirement::Any`, which has no single unmet requirement, the `Any` requirement itself is returned.
+    pub fn first_unmet<R: Role>(&self, token: &KeycloakToken<R>) -> Option<&Requirement> {
+        let satisfied = match self {
+            Requirement::Scope(scope) => token.has_scope(scope),
+            Requirement::Role(requirement) => token.satisfies(requirement),
+            Requirement::Group(group) => token.is_in_group(group),
+            Requirement::All(requirements) => {
+                return requirements
+                    .iter()
+                    .find_map(|requirement| requirement.first_unmet(token))
+            }
+            Requirement::Any(requirements) => requirements
+                .iter()
+                .any(|requirement| requirement.first_unmet(token).is_none()),
+        };
+        match satisfied {
+            true => None,
+            false => Some(self),
+        }
+    }
+}
+
+impl From<RoleRequirement> for Requirement {
+    fn from(requirement: RoleRequirement) -> Self {
+        Requirement::Role(requirement)
+    }
+}
+
+impl Display for Requirement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let write_all =
+            |f: &mut std::fmt::Formatter<'_>, name: &str, requirements: &[Requirement]| {
+                f.write_str(name)?;
+                f.write_str("(")?;
+                for (i, requirement) in requirements.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    Display::fmt(requirement, f)?;
+                }
+                f.write_str(")")
+            };
+        match self {
+            Requirement::Scope(scope) => write!(f, "scope({scope})"),
+            Requirement::Role(requirement) => Display::fmt(requirement, f),
+            Requirement::Group(group) => write!(f, "group({group})"),
+            Requirement::All(requirements) => write_all(f, "all", requirements),
+            Requirement::Any(requirements) => write_all(f, "any", requirements),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use crate::{
+        decode::KeycloakToken,
+        error::AuthError,
+        service::test::{claims, parse_token},
+    };
+
+    use super::Requirement;
+
+    fn token() -> KeycloakToken<String> {
+        let mut claims = claims();
+        claims["scope"] = json!("openid orders:read orders:write");
+        claims["groups"] = json!(["/engineering/backend"]);
+        parse_token(claims)
+    }
+
+    #[test]
+    fn reports_first_unmet_requirement() {
+        let token = token();
+
+        assert!(token
+            .authorize(&Requirement::new(
+                ["orders:write"],
+                ["administrator"],
+                ["/engineering/backend"]
+            ))
+            .is_ok());
+        assert!(matches!(
+            token.authorize(&Requirement::new(["orders:write", "orders:delete"], ["auditor"], Vec::<String>::new())),
+            Err(AuthError::UnmetRequirement { requirement }) if requirement == "scope(orders:delete)"
+        ));
+        assert!(token
+            .authorize(
+                &Requirement::scope("orders:delete").or(Requirement::group("/engineering/backend"))
+            )
+            .is_ok());
+        assert!(matches!(
+            token.authorize(&Requirement::group("/sales").or(Requirement::role("auditor"))),
+            Err(AuthError::UnmetRequirement { requirement }) if requirement == "any(group(/sales), role(auditor))"
+        ));
+    }
+
+    #[test]
+    fn deserializes_requirements() {
+        let requirement: Requirement = serde_json::from_value(json!({
+            "all": [{ "scope": "orders:write" }, { "role": { "realm_role": "clerk" } }]
+        }))
+        .expect("valid requirement");
+        assert_eq!(
+            requirement.to_string(),
+            "all(scope(orders:write), realm_role(clerk))"
+        );
+    }
+}
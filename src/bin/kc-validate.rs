@@ -0,0 +1,195 @@
+//! Validates a token using the exact logic of the `KeycloakAuthLayer`, printing its claims, roles and the verdict.
+//! Helps debugging rejected requests in production.
+//!
+//! Usage: `kc-validate (--key <public-key.pem> | --server <url> --realm <realm>) --audience <audience>... [token]`
+//!
+//! The token is verified with the realm's public key, either read from a PEM file or fetched from the Keycloak server,
+//! in which case the key is chosen by the token's key ID and the token must be issued by the realm.
+//! The token is read from stdin if it is omitted or given as `-`. Exits with 0 if the token is valid, with 1 if not,
+//! and with 2 on usage errors or if the keys cannot be fetched.
+
+use std::{io::Read, process::ExitCode, sync::Arc};
+
+use axum_keycloak_auth::{
+    decode::RawToken,
+    instance::{KeycloakAuthInstance, KeycloakConfig},
+    role::KeycloakRole,
+    service::KeycloakAuthLayer,
+};
+use jsonwebtoken::{DecodingKey, Validation};
+
+const USAGE: &str = "Usage: kc-validate (--key <public-key.pem> | --server <url> --realm <realm>) --audience <audience>... [token]";
+
+/// Where the keys to verify the token with are taken from.
+enum Keys {
+    Pem(String),
+    Keycloak { server: String, realm: String },
+}
+
+struct Args {
+    keys: Keys,
+    audiences: Vec<String>,
+    token: Option<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut key = None;
+    let mut server = None;
+    let mut realm = None;
+    let mut audiences = Vec::new();
+    let mut token = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--key" => key = Some(args.next().ok_or("Missing value of --key.")?),
+            "--server" => server = Some(args.next().ok_or("Missing value of --server.")?),
+            "--realm" => realm = Some(args.next().ok_or("Missing value of --realm.")?),
+            "--audience" => audiences.push(args.next().ok_or("Missing value of --audience.")?),
+            "-h" | "--help" => return Err(String::new()),
+            "-" => token = None,
+            _ if arg.starts_with("--") => return Err(format!("Unknown option '{arg}'.")),
+            _ => token = Some(arg),
+        }
+    }
+    let keys = match (key, server, realm) {
+        (Some(key), None, None) => Keys::Pem(key),
+        (None, Some(server), Some(realm)) => Keys::Keycloak { server, realm },
+        (None, Some(_), None) => return Err(String::from("Missing --realm.")),
+        (None, None, Some(_)) => return Err(String::from("Missing --server.")),
+        (None, None, None) => return Err(String::from("Missing --key, or --server and --realm.")),
+        (Some(_), _, _) => {
+            return Err(String::from(
+                "Pass either --key, or --server and --realm, not both.",
+            ))
+        }
+    };
+    Ok(Args {
+        keys,
+        audiences,
+        token,
+    })
+}
+
+/// Builds the layer, fetching the realm's keys from Keycloak if requested.
+async fn layer(keys: Keys, audiences: Vec<String>) -> Result<KeycloakAuthLayer<String>, String> {
+    let builder = KeycloakAuthLayer::<String>::builder();
+    Ok(match keys {
+        Keys::Pem(path) => builder
+            .decoding_key(Arc::new(decoding_key(&path)?))
+            .expected_audiences(audiences)
+            .build(),
+        Keys::Keycloak { server, realm } => {
+            let config = KeycloakConfig::builder()
+                .server(server)
+                .realm(realm)
+                .key_refresh_interval(None)
+                .build();
+            let instance = KeycloakAuthInstance::new(config)
+                .await
+                .map_err(|err| format!("Could not fetch the keys of the realm: {err}"))?;
+            builder
+                .instance(instance)
+                .expected_audiences(audiences)
+                .build()
+        }
+    })
+}
+
+fn decoding_key(path: &str) -> Result<DecodingKey, String> {
+    let pem = std::fs::read(path).map_err(|err| format!("Could not read '{path}': {err}"))?;
+    DecodingKey::from_rsa_pem(&pem)
+        .or_else(|_| DecodingKey::from_ec_pem(&pem))
+        .or_else(|_| DecodingKey::from_ed_pem(&pem))
+        .map_err(|err| format!("'{path}' contains no supported public key: {err}"))
+}
+
+/// The claims of the token, without verifying it, so that they can be shown for rejected tokens as well.
+fn print_claims(token: &RawToken<'_>) {
+    let Ok(header) = token.header() else {
+        return;
+    };
+    let mut validation = Validation::new(header.alg);
+    validation.insecure_disable_signature_validation();
+    validation.validate_aud = false;
+    validation.validate_exp = false;
+    validation.required_spec_claims.clear();
+    if let Ok(data) = jsonwebtoken::decode::<serde_json::Value>(
+        token.as_str(),
+        &DecodingKey::from_secret(&[]),
+        &validation,
+    ) {
+        println!("Header: {header:?}");
+        println!(
+            "Claims (unverified): {}",
+            serde_json::to_string_pretty(&data.claims).unwrap_or_default()
+        );
+    }
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(err) => {
+            if !err.is_empty() {
+                eprintln!("{err}");
+            }
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    let token = match args.token {
+        Some(token) => token,
+        None => {
+            let mut token = String::new();
+            if let Err(err) = std::io::stdin().read_to_string(&mut token) {
+                eprintln!("Could not read the token from stdin: {err}");
+                return ExitCode::from(2);
+            }
+            token
+        }
+    };
+    let runtime = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(err) => {
+            eprintln!("Could not start the runtime: {err}");
+            return ExitCode::from(2);
+        }
+    };
+    let layer = match runtime.block_on(layer(args.keys, args.audiences)) {
+        Ok(layer) => layer,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let token = match RawToken::try_from(token.trim()) {
+        Ok(token) => token,
+        Err(err) => {
+            println!("Verdict: INVALID ({err})");
+            return ExitCode::from(1);
+        }
+    };
+    print_claims(&token);
+
+    match runtime.block_on(layer.validate(token)) {
+        Ok(token) => {
+            println!("Roles:");
+            for role in token.roles.iter() {
+                match role {
+                    KeycloakRole::Realm { role } => println!("  realm: {role}"),
+                    KeycloakRole::Client { client, role } => println!("  client {client}: {role}"),
+                }
+            }
+            println!("Verdict: VALID ({token})");
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            println!("Verdict: INVALID ({err})");
+            ExitCode::from(1)
+        }
+    }
+}
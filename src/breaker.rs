@@ -0,0 +1,246 @@
+use std::{
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use typed_builder::TypedBuilder;
+
+use crate::error::AuthError;
+
+/// Configures the circuit breaker guarding requests to Keycloak, see `KeycloakConfig::circuit_breaker`.
+///
+/// After `failure_threshold` consecutive requests failed (with a connection error, a timeout or a 5xx or 429 status,
+/// after all retries and fallback servers), the circuit opens: Further requests fail immediately with `AuthError::UpstreamUnavailable`
+/// instead of waiting for an unreachable Keycloak. Once `open_duration` passed, a single request is let through as a probe.
+/// If it succeeds, the circuit closes again. Otherwise, it stays open for another `open_duration`.
+///
+/// Can be deserialized from configuration, e.g. `{ "failure_threshold": 3, "open_duration": "1m" }`. Absent fields take their defaults.
+#[derive(Debug, Clone, PartialEq, Eq, TypedBuilder, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CircuitBreakerPolicy {
+    /// Number of consecutive failed requests opening the circuit.
+    #[builder(default = 5)]
+    pub failure_threshold: u32,
+
+    /// How long the circuit stays open before a probe request is let through.
+    #[builder(default = Duration::from_secs(30))]
+    #[serde(with = "crate::duration")]
+    pub open_duration: Duration,
+}
+
+impl Default for CircuitBreakerPolicy {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Closed {
+        failures: u32,
+    },
+    Open {
+        until: Instant,
+    },
+    /// A probe request is in flight.
+    HalfOpen,
+}
+
+/// The state of the circuit breaker of a `KeycloakAuthInstance`. Lets all requests through if no policy is configured.
+#[derive(Debug)]
+pub(crate) struct CircuitBreaker {
+    policy: Option<CircuitBreakerPolicy>,
+    state: Mutex<State>,
+}
+
+impl CircuitBreaker {
+    pub(crate) fn new(policy: Option<CircuitBreakerPolicy>) -> Self {
+        Self {
+            policy,
+            state: Mutex::new(State::Closed { failures: 0 }),
+        }
+    }
+
+    /// Fails with `AuthError::UpstreamUnavailable` if no request may be sent to Keycloak right now.
+    /// Otherwise, the outcome of the request must be reported through the returned permit.
+    pub(crate) fn acquire(&self) -> Result<BreakerPermit<'_>, AuthError> {
+        let permit = BreakerPermit {
+            breaker: self,
+            completed: false,
+        };
+        let Some(policy) = &self.policy else {
+            return Ok(permit);
+        };
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let retry_after = match *state {
+            State::Closed { failures: _ } => return Ok(permit),
+            State::Open { until } => match until.checked_duration_since(Instant::now()) {
+                Some(retry_after) if !retry_after.is_zero() => retry_after,
+                _ => {
+                    tracing::info!("Probing whether Keycloak is reachable again");
+                    *state = State::HalfOpen;
+                    return Ok(permit);
+                }
+            },
+            State::HalfOpen => policy.open_duration,
+        };
+        #[cfg(feature = "metrics")]
+        metrics::counter!("keycloak_auth_circuit_breaker_rejected_total").increment(1);
+        Err(AuthError::UpstreamUnavailable { retry_after })
+    }
+
+    /// Records the outcome of a request let through by `acquire`.
+    /// `reachable` is `false` if Keycloak could not be reached or failed with a transient error.
+    fn record(&self, reachable: bool) {
+        let Some(policy) = &self.policy else {
+            return;
+        };
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let next = match (*state, reachable) {
+            (State::Closed { failures: _ }, true) => State::Closed { failures: 0 },
+            (State::Open { until: _ } | State::HalfOpen, true) => {
+                tracing::info!("Keycloak is reachable again. Closing the circuit.");
+                State::Closed { failures: 0 }
+            }
+            (State::Closed { failures }, false) if failures + 1 < policy.failure_threshold => {
+                State::Closed {
+                    failures: failures + 1,
+                }
+            }
+            (_, false) => {
+                tracing::warn!(
+                    open_duration = ?policy.open_duration,
+                    "Keycloak is unreachable. Opening the circuit, failing requests to Keycloak immediately."
+                );
+                State::Open {
+                    until: Instant::now() + policy.open_duration,
+                }
+            }
+        };
+        #[cfg(feature = "metrics")]
+        metrics::gauge!("keycloak_auth_circuit_breaker_open").set(match next {
+            State::Closed { failures: _ } => 0.0,
+            State::Open { until: _ } | State::HalfOpen => 1.0,
+        });
+        *state = next;
+    }
+
+    /// Handles a request let through by `acquire` which was cancelled before its outcome was known.
+    /// An abandoned probe keeps the circuit open for another `open_duration`, so that the next probe is let through afterwards.
+    fn abandon(&self) {
+        let Some(policy) = &self.policy else {
+            return;
+        };
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if *state == State::HalfOpen {
+            *state = State::Open {
+                until: Instant::now() + policy.open_duration,
+            };
+        }
+    }
+}
+
+/// Permission to send a request to Keycloak, granted by `CircuitBreaker::acquire`.
+/// Dropping it without calling `complete`, e.g. because the request future was dropped when a client disconnected,
+/// abandons the request, so that an interrupted probe does not leave the circuit half-open forever.
+#[derive(Debug)]
+pub(crate) struct BreakerPermit<'a> {
+    breaker: &'a CircuitBreaker,
+    completed: bool,
+}
+
+impl BreakerPermit<'_> {
+    /// Records the outcome of the request, see `CircuitBreaker::record`.
+    pub(crate) fn complete(mut self, reachable: bool) {
+        self.completed = true;
+        self.breaker.record(reachable);
+    }
+}
+
+impl Drop for BreakerPermit<'_> {
+    fn drop(&mut self) {
+        if !self.completed {
+            self.breaker.abandon();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::error::AuthError;
+
+    use super::{CircuitBreaker, CircuitBreakerPolicy};
+
+    #[test]
+    fn opens_after_consecutive_failures_and_probes_when_half_open() {
+        let breaker = CircuitBreaker::new(Some(
+            CircuitBreakerPolicy::builder()
+                .failure_threshold(2)
+                .open_duration(Duration::from_millis(50))
+                .build(),
+        ));
+        let request = |reachable: bool| breaker.acquire().expect("closed").complete(reachable);
+        request(false);
+        request(true);
+        request(false);
+        request(false);
+        assert!(matches!(
+            breaker.acquire(),
+            Err(AuthError::UpstreamUnavailable { retry_after }) if retry_after <= Duration::from_millis(50)
+        ));
+
+        std::thread::sleep(Duration::from_millis(60));
+        let probe = breaker.acquire().expect("probe");
+        // Only a single probe is let through.
+        assert!(breaker.acquire().is_err());
+        probe.complete(false);
+        assert!(breaker.acquire().is_err());
+
+        std::thread::sleep(Duration::from_millis(60));
+        breaker.acquire().expect("probe").complete(true);
+        assert!(breaker.acquire().is_ok());
+    }
+
+    #[tokio::test]
+    async fn reopens_when_the_probe_is_dropped() {
+        let breaker = CircuitBreaker::new(Some(
+            CircuitBreakerPolicy::builder()
+                .failure_threshold(1)
+                .open_duration(Duration::from_millis(50))
+                .build(),
+        ));
+        breaker.acquire().expect("closed").complete(false);
+        tokio::time::sleep(Duration::from_millis(60)).await;
+
+        // The probe request hangs until its future is dropped, e.g. because the client disconnected.
+        let probe = async {
+            let permit = breaker.acquire()?;
+            std::future::pending::<()>().await;
+            permit.complete(true);
+            Ok::<_, AuthError>(())
+        };
+        assert!(tokio::time::timeout(Duration::from_millis(10), probe)
+            .await
+            .is_err());
+
+        assert!(matches!(
+            breaker.acquire(),
+            Err(AuthError::UpstreamUnavailable { retry_after }) if retry_after <= Duration::from_millis(50)
+        ));
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        breaker.acquire().expect("probe").complete(true);
+        assert!(breaker.acquire().is_ok());
+    }
+
+    #[test]
+    fn lets_all_requests_through_without_policy() {
+        let breaker = CircuitBreaker::new(None);
+        for _ in 0..10 {
+            breaker.acquire().expect("no policy").complete(false);
+        }
+        assert!(breaker.acquire().is_ok());
+    }
+}
@@ -1,22 +1,57 @@
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::{Debug, Display};
+use std::ops::Deref;
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock, PoisonError, RwLock};
 
 use http::HeaderMap;
 use http::HeaderValue;
-use jsonwebtoken::{decode, decode_header, DecodingKey, Validation};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Header, Validation};
 use serde::de::value::MapDeserializer;
 use serde::{Deserialize, Serialize};
 use snafu::ResultExt;
 use tracing::debug;
 
+use crate::audience::Audience;
+use crate::authorize::Requirement;
 use crate::error::DecodeHeaderSnafu;
-use crate::error::DecodeSnafu;
+use crate::organization::{Organization, OrganizationClaim};
+use crate::permission::{LazyPermissions, PermissionMap};
+use crate::role::glob_matches;
 use crate::role::ExpectRoles;
 use crate::role::KeycloakRole;
+use crate::role::MissingRolesPolicy;
 use crate::role::NumRoles;
+use crate::role::RoleDiff;
+use crate::role::RoleRequirement;
+use crate::role::RoleSet;
+use crate::validator::TokenValidator;
+use crate::TimestampRangePolicy;
+use crate::{AcceptedTokenTypes, JtiFormat, KeycloakAuthStatus};
 
 use super::{error::AuthError, role::ExtractRoles, role::Role};
 
-pub(crate) struct RawToken<'a>(&'a str);
+/// A JWT, not yet verified in any way.
+///
+/// Obtain one from the 'Authorization' header of a request (`TryFrom<&HeaderMap>`), or directly from the token string
+/// (`FromStr` / `TryFrom<&str>`), e.g. when tokens arrive in message metadata instead of HTTP requests.
+/// With the `typed-header` feature, it also converts from and to a `TypedHeader<Authorization<Bearer>>`.
+/// Verify it using a `TokenValidator`, applying the same logic as the `KeycloakAuthLayer`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawToken<'a>(Cow<'a, str>);
+
+/// Whether `value` is a UUID in its hyphenated form, e.g. "8c5a6e5e-0b0c-4c6b-9e8e-1b2f4c4c2d1a".
+fn is_uuid(value: &str) -> bool {
+    value.len() == 36
+        && value.bytes().enumerate().all(|(i, b)| match i {
+            8 | 13 | 18 | 23 => b == b'-',
+            _ => b.is_ascii_hexdigit(),
+        })
+}
+
+/// Maximum accepted length of a token in bytes. Generous enough for tokens carrying thousands of roles.
+pub const MAX_TOKEN_LEN: usize = 64 * 1024;
 
 pub(crate) fn parse_jwt_token(headers: &HeaderMap<HeaderValue>) -> Result<RawToken<'_>, AuthError> {
     headers
@@ -26,32 +61,296 @@ pub(crate) fn parse_jwt_token(headers: &HeaderMap<HeaderValue>) -> Result<RawTok
         .map_err(|err| AuthError::InvalidAuthorizationHeader {
             reason: err.to_string(),
         })?
-        .strip_prefix("Bearer ")
+        .strip_prefix("Bearer")
         .ok_or(AuthError::MissingBearerToken)
-        .map(RawToken)
+        .and_then(|credentials| match credentials.strip_prefix(' ') {
+            // Some SDKs send "Bearer " when their token store is empty.
+            _ if credentials.trim().is_empty() => Err(AuthError::EmptyBearerToken),
+            Some(token) => RawToken::try_from(token),
+            None => Err(AuthError::MissingBearerToken),
+        })
+}
+
+impl<'a> TryFrom<&'a HeaderMap<HeaderValue>> for RawToken<'a> {
+    type Error = AuthError;
+
+    /// Extracts the token from a "Bearer {token}" 'Authorization' header.
+    fn try_from(headers: &'a HeaderMap<HeaderValue>) -> Result<Self, Self::Error> {
+        parse_jwt_token(headers)
+    }
+}
+
+impl<'a> TryFrom<&'a str> for RawToken<'a> {
+    type Error = AuthError;
+
+    /// Checks the structural shape of the token: At most `MAX_TOKEN_LEN` bytes, consisting of three non-empty,
+    /// dot-separated segments of base64url characters. Fails with `AuthError::MalformedToken` otherwise,
+    /// so that pathological inputs never reach the deeper parsing layers.
+    /// Whether the segments decode to a valid token is only checked when the token is decoded.
+    fn try_from(token: &'a str) -> Result<Self, Self::Error> {
+        let malformed = |reason: &str| AuthError::MalformedToken {
+            reason: reason.to_owned(),
+        };
+        if token.len() > MAX_TOKEN_LEN {
+            return Err(malformed("The token exceeds the maximum length."));
+        }
+        let segments = token.split('.').collect::<Vec<_>>();
+        if segments.len() != 3 {
+            return Err(malformed(
+                "A JWT must consist of three dot-separated segments.",
+            ));
+        }
+        if segments.iter().any(|segment| segment.is_empty()) {
+            return Err(malformed("A JWT must not contain empty segments."));
+        }
+        if !segments.iter().all(|segment| {
+            segment
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+        }) {
+            return Err(malformed("A JWT must only contain base64url characters."));
+        }
+        Ok(Self(Cow::Borrowed(token)))
+    }
+}
+
+#[cfg(feature = "typed-header")]
+impl<'a> TryFrom<&'a axum::headers::Authorization<axum::headers::authorization::Bearer>>
+    for RawToken<'a>
+{
+    type Error = AuthError;
+
+    /// Takes the token from an 'Authorization' header already extracted as a typed header,
+    /// checking its structural shape like `TryFrom<&str>`.
+    fn try_from(
+        authorization: &'a axum::headers::Authorization<axum::headers::authorization::Bearer>,
+    ) -> Result<Self, Self::Error> {
+        match authorization.token() {
+            token if token.trim().is_empty() => Err(AuthError::EmptyBearerToken),
+            token => RawToken::try_from(token),
+        }
+    }
+}
+
+#[cfg(feature = "typed-header")]
+impl TryFrom<axum::TypedHeader<axum::headers::Authorization<axum::headers::authorization::Bearer>>>
+    for RawToken<'static>
+{
+    type Error = AuthError;
+
+    fn try_from(
+        axum::TypedHeader(authorization): axum::TypedHeader<
+            axum::headers::Authorization<axum::headers::authorization::Bearer>,
+        >,
+    ) -> Result<Self, Self::Error> {
+        RawToken::try_from(&authorization).map(RawToken::into_owned)
+    }
+}
+
+#[cfg(feature = "typed-header")]
+impl TryFrom<&RawToken<'_>> for axum::headers::Authorization<axum::headers::authorization::Bearer> {
+    type Error = AuthError;
+
+    /// Creates a "Bearer {token}" 'Authorization' header, e.g. to forward the token to a downstream service.
+    fn try_from(token: &RawToken<'_>) -> Result<Self, Self::Error> {
+        Self::bearer(token.as_str()).map_err(|err| AuthError::InvalidAuthorizationHeader {
+            reason: err.to_string(),
+        })
+    }
+}
+
+impl FromStr for RawToken<'static> {
+    type Err = AuthError;
+
+    fn from_str(token: &str) -> Result<Self, Self::Err> {
+        RawToken::try_from(token).map(RawToken::into_owned)
+    }
 }
 
 impl<'a> RawToken<'a> {
-    pub fn decode(&self, jwt_decoding_key: &DecodingKey, expected_audiences: &[String]) -> Result<RawClaims, AuthError> {
-        let jwt_header = decode_header(self.0).context(DecodeHeaderSnafu {})?;
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_owned(self) -> RawToken<'static> {
+        RawToken(Cow::Owned(self.0.into_owned()))
+    }
+
+    /// Decodes the JWT header WITHOUT verifying the token.
+    pub fn header(&self) -> Result<Header, AuthError> {
+        decode_header(&self.0).context(DecodeHeaderSnafu {})
+    }
+
+    /// Verifies the token using `validator`, returning its claims.
+    pub fn validate(
+        &self,
+        validator: &(impl TokenValidator + ?Sized),
+    ) -> Result<RawClaims, AuthError> {
+        validator.validate(&self.0)
+    }
+
+    pub(crate) fn decode(
+        &self,
+        jwt_decoding_key: &DecodingKey,
+        validations: &ValidationCache,
+    ) -> Result<RawClaims, AuthError> {
+        let jwt_header = self.header()?;
 
         debug!(?jwt_header, "Decoded JWT header");
 
-        let mut validation = Validation::new(jwt_header.alg);
-        validation.set_audience(expected_audiences);
+        let validation = validations.get(jwt_header.alg);
 
         let token_data =
-            decode::<RawClaims>(self.0, jwt_decoding_key, &validation).context(DecodeSnafu {})?;
+            decode::<RawClaims>(&self.0, jwt_decoding_key, &validation).map_err(|err| match err
+                .kind()
+            {
+                jsonwebtoken::errors::ErrorKind::InvalidAudience => {
+                    self.log_audience_mismatch(&validation);
+                    AuthError::WrongAudience { source: err }
+                }
+                jsonwebtoken::errors::ErrorKind::InvalidIssuer => {
+                    AuthError::WrongIssuer { source: err }
+                }
+                _ => AuthError::Decode { source: err },
+            })?;
 
         let raw_claims = token_data.claims;
         debug!(?raw_claims, "Decoded JWT data");
 
         Ok(raw_claims)
     }
+
+    /// Decodes the claims WITHOUT verifying the token in any way.
+    /// Never base any decision on the result, other than choosing how to verify the token or what to log.
+    pub(crate) fn insecure_claims(&self) -> Option<RawClaims> {
+        let jwt_header = decode_header(&self.0).ok()?;
+        let mut validation = Validation::new(jwt_header.alg);
+        validation.insecure_disable_signature_validation();
+        validation.validate_aud = false;
+        validation.validate_exp = false;
+        validation.required_spec_claims.clear();
+        decode::<RawClaims>(&self.0, &DecodingKey::from_secret(&[]), &validation)
+            .ok()
+            .map(|token_data| token_data.claims)
+    }
+
+    /// Audience mismatches are the most common integration failure. Log what the token contained versus what was expected.
+    /// This information must never be part of the response.
+    fn log_audience_mismatch(&self, validation: &Validation) {
+        if !tracing::enabled!(tracing::Level::DEBUG) {
+            return;
+        }
+        // The signature was already verified. Only the audience check failed.
+        let mut insecure = validation.clone();
+        insecure.insecure_disable_signature_validation();
+        insecure.validate_aud = false;
+        let token_audiences =
+            decode::<RawClaims>(&self.0, &DecodingKey::from_secret(&[]), &insecure)
+                .ok()
+                .and_then(|token_data| token_data.claims.get("aud").cloned());
+        debug!(
+            ?token_audiences,
+            expected_audiences = ?validation.aud,
+            "Token audience does not match any expected audience. \
+            Make sure that the client scope used by the client contains an audience mapper adding one of the expected audiences."
+        );
+    }
+}
+
+/// Creating a `Validation` allocates. As the expected audiences never change over the lifetime of a layer,
+/// a prototype is created once per signing algorithm and reused for all subsequent tokens using that algorithm.
+#[derive(Debug)]
+pub(crate) struct ValidationCache {
+    expected_audiences: Vec<Audience>,
+    expected_issuers: Vec<String>,
+    leeway: std::time::Duration,
+    validate_audience: bool,
+    validations: RwLock<HashMap<Algorithm, Arc<Validation>>>,
+}
+
+impl ValidationCache {
+    pub(crate) fn new(expected_audiences: &[Audience]) -> Self {
+        Self {
+            expected_audiences: expected_audiences.to_vec(),
+            expected_issuers: Vec::new(),
+            leeway: std::time::Duration::from_secs(60),
+            validate_audience: true,
+            validations: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Does not check the 'aud' claim, e.g. for ID tokens, whose audience is only known per request.
+    pub(crate) fn without_audience(mut self) -> Self {
+        self.validate_audience = false;
+        self
+    }
+
+    /// Additionally requires the 'iss' claim to equal the `expected_issuer` or one of the `additional_issuers`, if any is set.
+    pub(crate) fn with_issuers(
+        mut self,
+        expected_issuer: Option<&str>,
+        additional_issuers: &[String],
+    ) -> Self {
+        self.expected_issuers = expected_issuer
+            .map(String::from)
+            .into_iter()
+            .chain(additional_issuers.iter().cloned())
+            .collect();
+        self
+    }
+
+    /// Tolerates `leeway` of clock skew when checking the 'exp' and 'nbf' claims.
+    pub(crate) fn with_leeway(mut self, leeway: std::time::Duration) -> Self {
+        self.leeway = leeway;
+        self
+    }
+
+    pub(crate) fn get(&self, alg: Algorithm) -> Arc<Validation> {
+        if let Some(validation) = self
+            .validations
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&alg)
+        {
+            return validation.clone();
+        }
+        self.validations
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(alg)
+            .or_insert_with(|| {
+                let mut validation = Validation::new(alg);
+                validation.leeway = self.leeway.as_secs();
+                validation.validate_nbf = true;
+                match self.validate_audience {
+                    true => validation.set_audience(&self.expected_audiences),
+                    false => validation.validate_aud = false,
+                }
+                if !self.expected_issuers.is_empty() {
+                    validation.set_issuer(&self.expected_issuers);
+                }
+                Arc::new(validation)
+            })
+            .clone()
+    }
 }
 
 pub type RawClaims = HashMap<String, serde_json::Value>;
 
+/// Checks that all `required` claims are present in `raw_claims` and are not `null`.
+pub(crate) fn expect_claims(raw_claims: &RawClaims, required: &[String]) -> Result<(), AuthError> {
+    match required.iter().find(|claim| {
+        raw_claims
+            .get(*claim)
+            .map_or(true, serde_json::Value::is_null)
+    }) {
+        Some(claim) => Err(AuthError::MissingRequiredClaim {
+            claim: claim.to_owned(),
+        }),
+        None => Ok(()),
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StandardClaims {
     /// Expiration time (unix timestamp).
@@ -62,14 +361,35 @@ pub struct StandardClaims {
     pub jti: String,
     /// Issuer (who created and signed this token). This is the UUID which uniquely identifies this user inside Keycloak.
     pub iss: String,
-    /// Audience (who or what the token is intended for).
-    pub aud: String,
+    /// Audience (who or what the token is intended for). Empty if the claim was absent or `null`.
+    #[serde(default, deserialize_with = "deserialize_null_as_default")]
+    pub aud: StringOrVecString,
     /// Subject (whom the token refers to).
     pub sub: String,
     /// Type of token.
     pub typ: String,
     /// Authorized party (the party to which this token was issued).
     pub azp: String,
+    /// Client ID (the OAuth client which requested this token), as issued in RFC 9068 tokens and Keycloak service account tokens.
+    pub client_id: Option<String>,
+
+    /// Keycloak: ID of the user session this token was issued for (until Keycloak 24).
+    pub session_state: Option<String>,
+    /// Session ID (ID of the user session this token was issued for, since Keycloak 25 replacing 'session_state').
+    pub sid: Option<String>,
+    /// Keycloak: ID of the client session this token was issued for (only issued by older Keycloak versions).
+    pub client_session: Option<String>,
+    /// ID of the device this token was issued to. Not issued by Keycloak itself, but commonly added using a protocol mapper.
+    pub device_id: Option<String>,
+
+    /// Keycloak: Organizations of the user (since Keycloak 24, requires an organization mapper).
+    pub organization: Option<OrganizationClaim>,
+    /// Space-separated OAuth scopes granted to the token.
+    #[serde(default, deserialize_with = "deserialize_null_as_default")]
+    pub scope: String,
+    /// Groups of the user, commonly added using Keycloak's group membership mapper.
+    #[serde(default, deserialize_with = "deserialize_null_as_default")]
+    pub groups: Vec<String>,
 
     /// Keycloak: Optional realm roles from Keycloak.
     pub realm_access: Option<RealmAccess>,
@@ -94,21 +414,214 @@ impl StandardClaims {
         Self::deserialize(MapDeserializer::new(raw_claims.into_iter()))
             .map_err(|err| AuthError::JsonParse { source: err })
     }
+
+    /// All audiences of this token.
+    pub fn audiences(&self) -> &[String] {
+        self.aud.as_slice()
+    }
+}
+
+/// A claim which may either hold a single string or an array of strings, as is the case for 'aud'.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum StringOrVecString {
+    String(String),
+    Vec(Vec<String>),
+}
+
+impl StringOrVecString {
+    /// All contained strings, regardless of the representation used in the token.
+    pub fn as_slice(&self) -> &[String] {
+        match self {
+            StringOrVecString::String(string) => std::slice::from_ref(string),
+            StringOrVecString::Vec(vec) => vec.as_slice(),
+        }
+    }
+
+    pub fn contains(&self, value: &str) -> bool {
+        self.as_slice().iter().any(|it| it == value)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.as_slice().is_empty()
+    }
+
+    pub fn into_vec(self) -> Vec<String> {
+        match self {
+            StringOrVecString::String(string) => vec![string],
+            StringOrVecString::Vec(vec) => vec,
+        }
+    }
+}
+
+impl Default for StringOrVecString {
+    fn default() -> Self {
+        StringOrVecString::Vec(Vec::new())
+    }
+}
+
+impl From<String> for StringOrVecString {
+    fn from(value: String) -> Self {
+        StringOrVecString::String(value)
+    }
+}
+
+impl From<Vec<String>> for StringOrVecString {
+    fn from(value: Vec<String>) -> Self {
+        StringOrVecString::Vec(value)
+    }
+}
+
+fn deserialize_null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
 }
 
 /// Access details.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Access {
     /// A list of role names.
     pub roles: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RealmAccess(pub Access);
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ResourceAccess(pub HashMap<String, Access>);
 
+/// Converts the unix timestamp of the `claim`, applying the `policy` if it is out of the range of `time::OffsetDateTime` (beyond the year 9999).
+fn parse_timestamp(
+    claim: &'static str,
+    timestamp: i64,
+    policy: TimestampRangePolicy,
+) -> Result<time::OffsetDateTime, AuthError> {
+    match (time::OffsetDateTime::from_unix_timestamp(timestamp), policy) {
+        (Ok(date_time), _) => Ok(date_time),
+        (Err(_), TimestampRangePolicy::Clamp) => {
+            debug!(claim, timestamp, "Clamping out of range timestamp");
+            Ok(match timestamp > 0 {
+                true => time::PrimitiveDateTime::MAX.assume_utc(),
+                false => time::PrimitiveDateTime::MIN.assume_utc(),
+            })
+        }
+        (Err(_), TimestampRangePolicy::Reject) => Err(AuthError::TimestampOutOfRange {
+            claim: String::from(claim),
+            timestamp,
+        }),
+    }
+}
+
+/// Rewrites known non-standard layouts of the 'resource_access' claim, as emitted by some identity brokers,
+/// into Keycloak's layout (`{ "<client>": { "roles": ["<role>"] } }`). Supported are
+/// - arrays of clients: `[{ "client": "<client>", "roles": [..] }]` (also using "client_id", "clientId" or "resource"),
+/// - roles listed directly: `{ "<client>": ["<role>"] }`,
+/// - roles given as objects: `{ "<client>": { "roles": [{ "name": "<role>" }] } }` (also using "role").
+///
+/// Claims which are already in Keycloak's layout, or in no known layout, are left untouched.
+pub fn normalize_resource_access(raw_claims: &mut RawClaims) {
+    if let Some(resource_access) = raw_claims.get_mut("resource_access") {
+        if let Some(normalized) = normalized_resource_access(resource_access) {
+            *resource_access = normalized;
+        }
+    }
+}
+
+/// Treats the entries of the 'groups' claim as realm roles, as some clusters (e.g. Kubernetes with OIDC) use groups as roles.
+/// This way, a single role-check API covers both conventions. See `KeycloakAuthLayer::groups_as_roles`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GroupsAsRoles {
+    strip_prefix: Option<String>,
+}
+
+impl GroupsAsRoles {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Strips `prefix` from groups starting with it, e.g. "/" from Keycloak's full group paths or "oidc:" from Kubernetes groups.
+    pub fn strip_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.strip_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Adds the groups of `raw_claims` to the roles of its 'realm_access' claim, creating the claim if necessary.
+    /// A 'realm_access' claim not in Keycloak's layout is left untouched.
+    pub fn apply(&self, raw_claims: &mut RawClaims) {
+        use serde_json::Value;
+
+        let Some(Value::Array(groups)) = raw_claims.get("groups") else {
+            return;
+        };
+        let roles = groups
+            .iter()
+            .filter_map(Value::as_str)
+            .map(|group| match &self.strip_prefix {
+                Some(prefix) => group.strip_prefix(prefix.as_str()).unwrap_or(group),
+                None => group,
+            })
+            .map(|role| Value::String(role.to_owned()))
+            .collect::<Vec<_>>();
+        if roles.is_empty() {
+            return;
+        }
+        let realm_access = raw_claims
+            .entry(String::from("realm_access"))
+            .or_insert_with(|| serde_json::json!({ "roles": [] }));
+        if let Some(Value::Array(realm_roles)) = realm_access.get_mut("roles") {
+            for role in roles {
+                if !realm_roles.contains(&role) {
+                    realm_roles.push(role);
+                }
+            }
+        }
+    }
+}
+
+fn normalized_resource_access(value: &serde_json::Value) -> Option<serde_json::Value> {
+    use serde_json::Value;
+
+    let clients = match value {
+        Value::Object(clients) => clients
+            .iter()
+            .map(|(client, access)| (client.as_str(), access))
+            .collect::<Vec<_>>(),
+        Value::Array(entries) => entries
+            .iter()
+            .map(|entry| {
+                ["client", "client_id", "clientId", "resource"]
+                    .iter()
+                    .find_map(|key| entry.get(key)?.as_str())
+                    .map(|client| (client, entry))
+            })
+            .collect::<Option<Vec<_>>>()?,
+        _ => return None,
+    };
+    let mut normalized = serde_json::Map::with_capacity(clients.len());
+    for (client, access) in clients {
+        let roles = match access {
+            Value::Array(roles) => roles,
+            Value::Object(access) => access.get("roles")?.as_array()?,
+            _ => return None,
+        };
+        let roles = roles
+            .iter()
+            .map(|role| match role {
+                Value::String(role) => Some(role.as_str()),
+                Value::Object(role) => ["name", "role"]
+                    .iter()
+                    .find_map(|key| role.get(*key)?.as_str()),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        normalized.insert(client.to_owned(), serde_json::json!({ "roles": roles }));
+    }
+    Some(Value::Object(normalized))
+}
+
 impl NumRoles for RealmAccess {
     fn num_roles(&self) -> usize {
         self.0.roles.len()
@@ -129,7 +642,23 @@ impl<R: Role> ExtractRoles<R> for RealmAccess {
     }
 }
 
+impl<R: Role> ExtractRoles<R> for &RealmAccess {
+    fn extract_roles(self, target: &mut Vec<KeycloakRole<R>>) {
+        for role in &self.0.roles {
+            target.push(KeycloakRole::Realm {
+                role: role.to_owned().into(),
+            });
+        }
+    }
+}
+
 impl<R: Role> ExtractRoles<R> for ResourceAccess {
+    fn extract_roles(self, target: &mut Vec<KeycloakRole<R>>) {
+        (&self).extract_roles(target)
+    }
+}
+
+impl<R: Role> ExtractRoles<R> for &ResourceAccess {
     fn extract_roles(self, target: &mut Vec<KeycloakRole<R>>) {
         for (res_name, access) in &self.0 {
             for role in &access.roles {
@@ -142,8 +671,159 @@ impl<R: Role> ExtractRoles<R> for ResourceAccess {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+/// Roles of a token. These are only extracted from the token's 'realm_access' and 'resource_access' claims
+/// when first accessed, sparing routes which never check roles the allocations involved.
+///
+/// Dereferences to a slice of all roles.
+#[derive(Debug, Clone)]
+pub struct LazyRoles<R: Role> {
+    realm_access: Option<RealmAccess>,
+    resource_access: Option<ResourceAccess>,
+    extracted: OnceLock<Vec<KeycloakRole<R>>>,
+    /// Index of the extracted roles, built on the first presence check.
+    set: OnceLock<RoleSet<R>>,
+}
+
+impl<R: Role> LazyRoles<R> {
+    pub fn new(realm_access: Option<RealmAccess>, resource_access: Option<ResourceAccess>) -> Self {
+        Self {
+            realm_access,
+            resource_access,
+            extracted: OnceLock::new(),
+            set: OnceLock::new(),
+        }
+    }
+
+    /// All roles, extracting them on first access.
+    pub fn as_slice(&self) -> &[KeycloakRole<R>] {
+        self.as_vec()
+    }
+
+    fn as_vec(&self) -> &Vec<KeycloakRole<R>> {
+        self.extracted.get_or_init(|| {
+            let mut roles = Vec::new();
+            (self.realm_access.as_ref(), self.resource_access.as_ref()).extract_roles(&mut roles);
+            roles
+        })
+    }
+
+    /// All distinct roles, indexed on first access. Used for all presence checks, as these are memoized this way.
+    pub fn as_set(&self) -> &RoleSet<R> {
+        self.set.get_or_init(|| RoleSet::new(self.as_slice()))
+    }
+
+    /// Whether `role` is present as a realm role or as a client role of any client.
+    pub fn contains(&self, role: &R) -> bool {
+        self.as_set().contains(role)
+    }
+
+    /// Whether these roles were read from a token carrying at least one of the 'realm_access' and 'resource_access' claims.
+    pub fn has_access_claims(&self) -> bool {
+        self.has_source()
+    }
+
+    fn has_source(&self) -> bool {
+        self.realm_access.is_some() || self.resource_access.is_some()
+    }
+}
+
+/// Counts without extracting the roles, if they were not already extracted.
+impl<R: Role> NumRoles for LazyRoles<R> {
+    fn num_roles(&self) -> usize {
+        match self.extracted.get() {
+            Some(roles) => roles.len(),
+            None => self.realm_access.num_roles() + self.resource_access.num_roles(),
+        }
+    }
+}
+
+impl<R: Role> From<Vec<KeycloakRole<R>>> for LazyRoles<R> {
+    fn from(roles: Vec<KeycloakRole<R>>) -> Self {
+        Self {
+            realm_access: None,
+            resource_access: None,
+            extracted: OnceLock::from(roles),
+            set: OnceLock::new(),
+        }
+    }
+}
+
+impl<R: Role> Deref for LazyRoles<R> {
+    type Target = [KeycloakRole<R>];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl<R: Role> From<LazyRoles<R>> for Vec<KeycloakRole<R>> {
+    fn from(roles: LazyRoles<R>) -> Self {
+        roles.extracted.into_inner().unwrap_or_else(|| {
+            let mut extracted = Vec::new();
+            (roles.realm_access, roles.resource_access).extract_roles(&mut extracted);
+            extracted
+        })
+    }
+}
+
+/// Roles are compared as sets, as client roles are extracted in no particular order
+/// and the order of roles carries no meaning in Keycloak.
+impl<R: Role> PartialEq for LazyRoles<R> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().collect::<HashSet<_>>() == other.iter().collect::<HashSet<_>>()
+    }
+}
+
+/// Type of a token, as stated by its 'typ' claim.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum TokenType {
+    /// An access token ('typ' is "Bearer").
+    Access,
+    /// An ID token ('typ' is "ID").
+    Id,
+    /// Any other type of token, e.g. a refresh token.
+    Other(String),
+}
+
+impl From<String> for TokenType {
+    fn from(typ: String) -> Self {
+        match typ.as_str() {
+            "Bearer" => TokenType::Access,
+            "ID" => TokenType::Id,
+            _ => TokenType::Other(typ),
+        }
+    }
+}
+
+impl std::fmt::Display for TokenType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenType::Access => f.write_str("Bearer"),
+            TokenType::Id => f.write_str("ID"),
+            TokenType::Other(typ) => f.write_str(typ),
+        }
+    }
+}
+
+/// A validated and parsed token.
+///
+/// All data is shared behind an `Arc`, making clones cheap. Tokens can therefore be freely handed to spawned tasks,
+/// channels or background jobs. The token dereferences to its `KeycloakTokenParts`, giving direct access to all fields.
+#[derive(Clone)]
 pub struct KeycloakToken<R: Role> {
+    inner: Arc<KeycloakTokenInner<R>>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+struct KeycloakTokenInner<R: Role> {
+    parts: KeycloakTokenParts<R>,
+    /// Application permissions, resolved from the roles when first accessed.
+    permissions: LazyPermissions,
+}
+
+/// All data of a `KeycloakToken`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct KeycloakTokenParts<R: Role> {
     /// Expiration time (UTC).
     pub expires_at: time::OffsetDateTime,
     /// Issued at time (UTC).
@@ -153,14 +833,36 @@ pub struct KeycloakToken<R: Role> {
     /// Issuer (who created and signed this token).
     pub issuer: String,
     /// Audience (who or what the token is intended for).
-    pub audience: String,
+    pub audience: StringOrVecString,
     /// Subject (whom the token refers to). This is the UUID which uniquely identifies this user inside Keycloak.
     pub subject: String,
     /// Authorized party (the party to which this token was issued).
     pub authorized_party: String,
+    /// ID of the OAuth client which requested this token ('client_id' claim, RFC 9068 and Keycloak service accounts).
+    /// Use `KeycloakToken::client` to get the client of tokens of any profile.
+    pub client_id: Option<String>,
+    /// Type of token.
+    pub token_type: TokenType,
 
-    // Keycloak: Roles of the user.
-    pub roles: Vec<KeycloakRole<R>>,
+    /// Keycloak: ID of the user session this token was issued for ('session_state' claim, until Keycloak 24).
+    pub session_state: Option<String>,
+    /// ID of the user session this token was issued for ('sid' claim, since Keycloak 25).
+    pub session_id: Option<String>,
+    /// Keycloak: ID of the client session this token was issued for ('client_session' claim, only issued by older Keycloak versions).
+    pub client_session: Option<String>,
+    /// ID of the device this token was issued to ('device_id' claim), if added by a protocol mapper.
+    pub device_id: Option<String>,
+
+    /// Keycloak: Organizations the user is a member of ('organization' claim, since Keycloak 24).
+    /// Empty if the claim was absent.
+    pub organizations: Vec<Organization>,
+    /// OAuth scopes granted to the token ('scope' claim). Empty if the claim was absent.
+    pub scopes: Vec<String>,
+    /// Groups of the user ('groups' claim, added by a group membership mapper). Empty if the claim was absent.
+    pub groups: Vec<String>,
+
+    /// Keycloak: Roles of the user.
+    pub roles: LazyRoles<R>,
     /// Keycloak: First name.
     pub given_name: String,
     /// Keycloak: Last name.
@@ -173,42 +875,156 @@ pub struct KeycloakToken<R: Role> {
     pub email: String,
     /// Keycloak: Whether the users email is verified.
     pub email_verified: bool,
+
+    /// Resolves the application permissions of this token, see `KeycloakToken::permissions`.
+    pub permission_map: Option<Arc<PermissionMap>>,
+}
+
+impl<R: Role> Deref for KeycloakToken<R> {
+    type Target = KeycloakTokenParts<R>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner.parts
+    }
+}
+
+impl<R: Role> PartialEq for KeycloakToken<R> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner) || self.inner == other.inner
+    }
+}
+
+impl<R: Role> Debug for KeycloakToken<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.parts.fmt(f)
+    }
+}
+
+/// A concise, PII-safe one-line description of the token. See `KeycloakToken::summary`.
+impl<R: Role> Display for KeycloakToken<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let remaining = (self.expires_at - time::OffsetDateTime::now_utc()).whole_seconds();
+        write!(
+            f,
+            "sub={}, azp={}, roles={}, ",
+            self.subject,
+            self.authorized_party,
+            self.num_roles()
+        )?;
+        match remaining >= 0 {
+            true => write!(f, "exp in {remaining}s"),
+            false => write!(f, "expired {}s ago", -remaining),
+        }
+    }
 }
 
 impl<R: Role> KeycloakToken<R> {
-    pub(crate) fn parse(raw: StandardClaims) -> Result<Self, AuthError> {
-        Ok(Self {
-            expires_at: time::OffsetDateTime::from_unix_timestamp(raw.exp).map_err(|err| {
-                AuthError::InvalidToken {
-                    reason: format!(
-                        "Could not parse 'exp' (expires_at) field as unix timestamp: {err}"
-                    ),
-                }
-            })?,
-            issued_at: time::OffsetDateTime::from_unix_timestamp(raw.iat).map_err(|err| {
-                AuthError::InvalidToken {
-                    reason: format!(
-                        "Could not parse 'iat' (issued_at) field as unix timestamp: {err}"
-                    ),
-                }
-            })?,
+    /// A concise one-line description of this token for request logs and error reports,
+    /// e.g. `sub=..., azp=..., roles=3, exp in 120s`.
+    /// Only contains the (pseudonymous) subject ID, never names, usernames or email addresses.
+    pub fn summary(&self) -> String {
+        self.to_string()
+    }
+
+    pub fn from_parts(parts: KeycloakTokenParts<R>) -> Self {
+        Self {
+            inner: Arc::new(KeycloakTokenInner {
+                permissions: parts
+                    .permission_map
+                    .clone()
+                    .map(LazyPermissions::new)
+                    .unwrap_or_default(),
+                parts,
+            }),
+        }
+    }
+
+    /// Returns the token's data, only copying it if this token is shared.
+    pub fn into_parts(self) -> KeycloakTokenParts<R> {
+        match Arc::try_unwrap(self.inner) {
+            Ok(inner) => inner.parts,
+            Err(shared) => shared.parts.clone(),
+        }
+    }
+
+    pub(crate) fn parse(
+        raw: StandardClaims,
+        timestamp_range_policy: TimestampRangePolicy,
+    ) -> Result<Self, AuthError> {
+        Ok(Self::from_parts(KeycloakTokenParts {
+            expires_at: parse_timestamp("exp", raw.exp, timestamp_range_policy)?,
+            issued_at: parse_timestamp("iat", raw.iat, timestamp_range_policy)?,
             jwt_id: raw.jti,
             issuer: raw.iss,
             audience: raw.aud,
             subject: raw.sub,
             authorized_party: raw.azp,
-            roles: {
-                let mut roles = Vec::new();
-                (raw.realm_access, raw.resource_access).extract_roles(&mut roles);
-                roles
-            },
+            client_id: raw.client_id,
+            token_type: raw.typ.into(),
+            session_state: raw.session_state,
+            session_id: raw.sid,
+            client_session: raw.client_session,
+            device_id: raw.device_id,
+            organizations: raw
+                .organization
+                .map(OrganizationClaim::into_organizations)
+                .unwrap_or_default(),
+            scopes: raw.scope.split_whitespace().map(String::from).collect(),
+            groups: raw.groups,
+            roles: LazyRoles::new(raw.realm_access, raw.resource_access),
             given_name: raw.given_name,
             family_name: raw.family_name,
             full_name: raw.name,
             preferred_username: raw.preferred_username,
             email_verified: raw.email_verified,
             email: raw.email,
-        })
+            permission_map: None,
+        }))
+    }
+
+    /// All audiences of this token.
+    pub fn audiences(&self) -> &[String] {
+        self.audience.as_slice()
+    }
+
+    /// Fails if this token was not issued for all of the `expected` audiences.
+    pub fn assert_all_audiences(&self, expected: &[Audience]) -> Result<(), AuthError> {
+        match expected
+            .iter()
+            .all(|audience| audience.is_contained_in(&self.audience))
+        {
+            true => Ok(()),
+            false => {
+                debug!(
+                    token_audiences = ?self.audiences(),
+                    expected_audiences = ?expected,
+                    "Token audience does not contain all expected audiences."
+                );
+                Err(AuthError::WrongAudience {
+                    source: jsonwebtoken::errors::ErrorKind::InvalidAudience.into(),
+                })
+            }
+        }
+    }
+
+    /// The client this token was issued to: its 'client_id' claim if present, its authorized party ('azp' claim) otherwise.
+    pub fn client(&self) -> &str {
+        self.client_id.as_deref().unwrap_or(&self.authorized_party)
+    }
+
+    /// Fails with `AuthError::ClientNotAllowed` unless this token was issued to one of the `allowed` clients (see `client`).
+    pub fn assert_client_allowed(&self, allowed: &[String]) -> Result<(), AuthError> {
+        match allowed.iter().any(|client| client == self.client()) {
+            true => Ok(()),
+            false => Err(AuthError::ClientNotAllowed {
+                client: self.client().to_owned(),
+            }),
+        }
+    }
+
+    /// ID of the Keycloak user session this token was issued for, regardless of the Keycloak version which issued it.
+    pub fn session(&self) -> Option<&str> {
+        self.session_id.as_deref().or(self.session_state.as_deref())
     }
 
     pub fn is_expired(&self) -> bool {
@@ -216,11 +1032,347 @@ impl<R: Role> KeycloakToken<R> {
     }
 
     pub fn assert_not_expired(&self) -> Result<(), AuthError> {
-        match self.is_expired() {
+        self.assert_not_expired_within(std::time::Duration::ZERO)
+    }
+
+    /// Like `assert_not_expired`, but tolerating a clock skew of up to `leeway`.
+    pub fn assert_not_expired_within(&self, leeway: std::time::Duration) -> Result<(), AuthError> {
+        match time::OffsetDateTime::now_utc() - leeway > self.expires_at {
             true => Err(AuthError::TokenExpired),
             false => Ok(()),
         }
     }
+
+    /// How far in the future this token was issued ('iat' claim), or `None` if it was not issued in the future.
+    pub fn issued_in_future(&self) -> Option<std::time::Duration> {
+        (self.issued_at - time::OffsetDateTime::now_utc())
+            .try_into()
+            .ok()
+            .filter(|skew: &std::time::Duration| !skew.is_zero())
+    }
+
+    /// Fails with `AuthError::TokenIssuedInFuture` if this token was issued further than `leeway` in the future.
+    pub fn assert_not_issued_in_future(
+        &self,
+        leeway: std::time::Duration,
+    ) -> Result<(), AuthError> {
+        match self.issued_in_future() {
+            Some(skew) if skew > leeway => Err(AuthError::TokenIssuedInFuture { skew }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Applies `policy` if this token carries neither a 'realm_access' nor a 'resource_access' claim.
+    pub fn apply_missing_roles_policy(
+        &mut self,
+        policy: &MissingRolesPolicy<R>,
+    ) -> Result<(), AuthError> {
+        if self.roles.has_access_claims() {
+            return Ok(());
+        }
+        match policy {
+            MissingRolesPolicy::AcceptEmpty => Ok(()),
+            MissingRolesPolicy::Reject => Err(AuthError::MissingRoles),
+            MissingRolesPolicy::DefaultRole(role) => {
+                Arc::make_mut(&mut self.inner).parts.roles =
+                    LazyRoles::from(vec![KeycloakRole::Realm { role: role.clone() }]);
+                Ok(())
+            }
+        }
+    }
+
+    /// Checks that the JWT ID ('jti' claim) of this token is in the given `format`.
+    pub fn assert_jti_format(&self, format: JtiFormat) -> Result<(), AuthError> {
+        let is_valid = match format {
+            JtiFormat::Any => true,
+            JtiFormat::Uuid => {
+                let uuid = match self.jwt_id.split_once(':') {
+                    Some((prefix, uuid)) if prefix.bytes().all(|b| b.is_ascii_alphanumeric()) => {
+                        uuid
+                    }
+                    _ => self.jwt_id.as_str(),
+                };
+                is_uuid(uuid)
+            }
+        };
+        match is_valid {
+            true => Ok(()),
+            false => Err(AuthError::InvalidJti {
+                jti: self.jwt_id.clone(),
+            }),
+        }
+    }
+
+    /// Checks that this token is of one of the `accepted` types.
+    /// ID tokens must additionally be intended for the party they were issued to,
+    /// as only then they are proof of an authentication performed for that party.
+    pub fn assert_token_type(&self, accepted: AcceptedTokenTypes) -> Result<(), AuthError> {
+        let is_accepted = matches!(
+            (&self.token_type, accepted),
+            (
+                TokenType::Access,
+                AcceptedTokenTypes::AccessOnly | AcceptedTokenTypes::Either
+            ) | (
+                TokenType::Id,
+                AcceptedTokenTypes::IdOnly | AcceptedTokenTypes::Either
+            )
+        );
+        if !is_accepted {
+            return Err(AuthError::UnexpectedTokenType {
+                typ: self.token_type.to_string(),
+            });
+        }
+        if self.token_type == TokenType::Id && !self.audience.contains(&self.authorized_party) {
+            return Err(AuthError::InvalidToken {
+                reason: String::from(
+                    "ID token was not issued for its authorized party ('aud' does not contain 'azp')",
+                ),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Summaries of the token's roles, e.g. for logging or admin tooling.
+impl<R: Role> KeycloakToken<R> {
+    /// All roles, as the `Vec` formerly stored in the `roles` field.
+    #[deprecated(
+        since = "0.3.0",
+        note = "`roles` is now a `LazyRoles`, dereferencing to a slice of all roles. Use the field instead."
+    )]
+    pub fn roles(&self) -> &Vec<KeycloakRole<R>> {
+        self.roles.as_vec()
+    }
+
+    /// The total number of realm and client roles. See `NumRoles`.
+    pub fn num_roles(&self) -> usize {
+        self.roles.num_roles()
+    }
+
+    /// Whether at least one of the given roles is present.
+    pub fn has_any_role<I: Into<R> + Clone>(&self, roles: &[I]) -> bool {
+        roles.iter().any(|expected| {
+            let expected: R = expected.clone().into();
+            self.roles.contains(&expected)
+        })
+    }
+
+    /// A stable hash of all roles, independent of their order. Equal for tokens carrying the same roles,
+    /// and stable across processes and versions of this crate, so it may be stored in shared caches.
+    pub fn roles_hash(&self) -> u64 {
+        let mut roles = self
+            .roles
+            .iter()
+            .map(|role| match role {
+                KeycloakRole::Realm { role } => format!("realm:{role}"),
+                KeycloakRole::Client { client, role } => format!("client:{client}:{role}"),
+            })
+            .collect::<Vec<_>>();
+        roles.sort_unstable();
+        roles.dedup();
+        fnv1a(roles.iter().flat_map(|role| role.bytes().chain([b'\n'])))
+    }
+
+    /// A key for caching a response to this token's user, e.g. with tower-http caching layers.
+    /// Combines the `base` key (e.g. method and URI) with the token's issuer, subject and `roles_hash`,
+    /// so that responses are never served to another user, nor to the same user after their roles changed.
+    pub fn cache_key(&self, base: &str) -> String {
+        format!(
+            "{base}|iss={:016x}|sub={}|roles={:016x}",
+            fnv1a(self.issuer.bytes()),
+            self.subject,
+            self.roles_hash()
+        )
+    }
+
+    /// The roles added and removed in `newer` compared to this token, e.g. after a token refresh.
+    pub fn role_diff(&self, newer: &KeycloakToken<R>) -> RoleDiff<R> {
+        RoleDiff::new(&self.roles, &newer.roles)
+    }
+
+    /// All realm roles.
+    pub fn realm_roles(&self) -> impl Iterator<Item = &R> {
+        self.roles
+            .iter()
+            .filter(|role| role.is_realm_role())
+            .map(KeycloakRole::role)
+    }
+
+    /// All client roles, grouped by client ID. Clients are ordered by their ID.
+    pub fn roles_by_client(&self) -> BTreeMap<&str, Vec<&R>> {
+        let mut by_client = BTreeMap::<&str, Vec<&R>>::new();
+        for role in self.roles.iter() {
+            if let Some(client) = role.client() {
+                by_client.entry(client).or_default().push(role.role());
+            }
+        }
+        by_client
+    }
+}
+
+/// Organization checks, available if an organization mapper adds the 'organization' claim (since Keycloak 24).
+impl<R: Role> KeycloakToken<R> {
+    /// The organization with the given alias, if the user is a member of it.
+    pub fn organization(&self, alias: &str) -> Option<&Organization> {
+        self.organizations
+            .iter()
+            .find(|organization| organization.alias == alias)
+    }
+
+    pub fn is_member_of(&self, alias: &str) -> bool {
+        self.organization(alias).is_some()
+    }
+
+    /// Fails if the user is not a member of the organization with the given alias.
+    pub fn expect_organization(&self, alias: &str) -> Result<&Organization, AuthError> {
+        self.organization(alias)
+            .ok_or_else(|| AuthError::MissingOrganization {
+                organization: alias.to_owned(),
+            })
+    }
+
+    /// Fails if the user is not a member of the organization with the given alias, or lacks the `role` within it.
+    pub fn expect_organization_role(
+        &self,
+        alias: &str,
+        role: &str,
+    ) -> Result<&Organization, AuthError> {
+        let organization = self.expect_organization(alias)?;
+        match organization.has_role(role) {
+            true => Ok(organization),
+            false => Err(AuthError::MissingOrganizationRole {
+                organization: alias.to_owned(),
+                role: role.to_owned(),
+            }),
+        }
+    }
+}
+
+/// Permission checks, available if the `KeycloakAuthLayer` was configured with a `PermissionMap`.
+impl<R: Role> KeycloakToken<R> {
+    /// Resolves permissions using `map` from now on.
+    pub fn with_permission_map(mut self, map: Arc<PermissionMap>) -> Self {
+        let inner = Arc::make_mut(&mut self.inner);
+        inner.parts.permission_map = Some(map.clone());
+        inner.permissions = LazyPermissions::new(map);
+        self
+    }
+
+    /// All permissions granted by the roles of this token. Empty if no `PermissionMap` is configured.
+    pub fn permissions(&self) -> &BTreeSet<String> {
+        self.inner.permissions.get(&self.roles)
+    }
+
+    pub fn has_permission(&self, permission: &str) -> bool {
+        self.permissions().contains(permission)
+    }
+
+    /// Fails with `AuthError::MissingPermission` if the given permission is not granted.
+    pub fn expect_permission(&self, permission: &str) -> Result<(), AuthError> {
+        match self.has_permission(permission) {
+            true => Ok(()),
+            false => Err(AuthError::MissingPermission {
+                permission: permission.to_owned(),
+            }),
+        }
+    }
+}
+
+/// Role checks by pattern, for role names which can not be enumerated statically (e.g. when encoding tenant ids).
+/// Patterns are matched against the `Display` representation of each role.
+impl<R: Role> KeycloakToken<R> {
+    /// Whether any role matches the glob `pattern`. See `role::glob_matches` for the supported syntax.
+    pub fn has_role_matching(&self, pattern: &str) -> bool {
+        self.roles
+            .iter()
+            .any(|role| glob_matches(pattern, &role.role().to_string()))
+    }
+
+    /// Fails with `AuthError::MissingExpectedRole` if no role matches the glob `pattern`.
+    pub fn expect_role_matching(&self, pattern: &str) -> Result<(), AuthError> {
+        match self.has_role_matching(pattern) {
+            true => Ok(()),
+            false => Err(AuthError::MissingExpectedRole {
+                role: pattern.to_owned(),
+            }),
+        }
+    }
+
+    /// Whether any role matches the given regular expression.
+    #[cfg(feature = "regex")]
+    pub fn has_role_matching_regex(&self, regex: &regex::Regex) -> bool {
+        self.roles
+            .iter()
+            .any(|role| regex.is_match(&role.role().to_string()))
+    }
+
+    /// Fails with `AuthError::MissingExpectedRole` if no role matches the given regular expression.
+    #[cfg(feature = "regex")]
+    pub fn expect_role_matching_regex(&self, regex: &regex::Regex) -> Result<(), AuthError> {
+        match self.has_role_matching_regex(regex) {
+            true => Ok(()),
+            false => Err(AuthError::MissingExpectedRole {
+                role: regex.as_str().to_owned(),
+            }),
+        }
+    }
+}
+
+impl<R: Role> KeycloakToken<R> {
+    /// Whether the roles of this token satisfy the `requirement`.
+    pub fn satisfies(&self, requirement: &RoleRequirement) -> bool {
+        match requirement {
+            RoleRequirement::Role(role) => self.roles.contains(&R::from(role.clone())),
+            RoleRequirement::RealmRole(role) => {
+                let role = R::from(role.clone());
+                self.realm_roles().any(|it| *it == role)
+            }
+            RoleRequirement::ClientRole { client, role } => {
+                let role = R::from(role.clone());
+                self.roles.iter().any(|it| it.matches(Some(client), &role))
+            }
+            RoleRequirement::Matching(pattern) => self.has_role_matching(pattern),
+            RoleRequirement::AllOf(requirements) => requirements
+                .iter()
+                .all(|requirement| self.satisfies(requirement)),
+            RoleRequirement::AnyOf(requirements) => requirements
+                .iter()
+                .any(|requirement| self.satisfies(requirement)),
+            RoleRequirement::Not(requirement) => !self.satisfies(requirement),
+        }
+    }
+
+    /// Whether the token was granted the OAuth `scope`.
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|it| it == scope)
+    }
+
+    /// Whether the user is a member of the `group`, e.g. "/engineering/backend".
+    pub fn is_in_group(&self, group: &str) -> bool {
+        self.groups.iter().any(|it| it == group)
+    }
+
+    /// Checks a combination of scope, role and group requirements in one call.
+    /// Fails with `AuthError::UnmetRequirement`, naming the first requirement this token does not satisfy.
+    /// See `Requirement` for more information.
+    pub fn authorize(&self, requirement: &Requirement) -> Result<(), AuthError> {
+        match requirement.first_unmet(self) {
+            None => Ok(()),
+            Some(unmet) => Err(AuthError::UnmetRequirement {
+                requirement: unmet.to_string(),
+            }),
+        }
+    }
+
+    /// Fails with `AuthError::MissingExpectedRole`, naming the whole requirement, if the roles of this token do not satisfy the `requirement`.
+    pub fn expect_requirement(&self, requirement: &RoleRequirement) -> Result<(), AuthError> {
+        match self.satisfies(requirement) {
+            true => Ok(()),
+            false => Err(AuthError::MissingExpectedRole {
+                role: requirement.to_string(),
+            }),
+        }
+    }
 }
 
 impl<R: Role> ExpectRoles<R> for KeycloakToken<R> {
@@ -229,7 +1381,7 @@ impl<R: Role> ExpectRoles<R> for KeycloakToken<R> {
     fn expect_roles<I: Into<R> + Clone>(&self, roles: &[I]) -> Result<(), Self::Rejection> {
         for expected in roles {
             let expected: R = expected.clone().into();
-            if !self.roles.iter().any(|role| role.role() == &expected) {
+            if !self.roles.contains(&expected) {
                 return Err(AuthError::MissingExpectedRole {
                     role: expected.to_string(),
                 });
@@ -241,10 +1393,576 @@ impl<R: Role> ExpectRoles<R> for KeycloakToken<R> {
     fn not_expect_roles<I: Into<R> + Clone>(&self, roles: &[I]) -> Result<(), Self::Rejection> {
         for expected in roles {
             let expected: R = expected.clone().into();
-            if let Some(_role) = self.roles.iter().find(|role| role.role() == &expected) {
+            if self.roles.contains(&expected) {
                 return Err(AuthError::UnexpectedRole);
             }
         }
         Ok(())
     }
 }
+
+/// A missing token is treated as carrying no roles at all.
+impl<R: Role> ExpectRoles<R> for Option<KeycloakToken<R>> {
+    type Rejection = AuthError;
+
+    fn expect_roles<I: Into<R> + Clone>(&self, roles: &[I]) -> Result<(), Self::Rejection> {
+        match self {
+            Some(token) => token.expect_roles(roles),
+            None => expect_roles_of_missing_token(roles),
+        }
+    }
+
+    fn not_expect_roles<I: Into<R> + Clone>(&self, roles: &[I]) -> Result<(), Self::Rejection> {
+        match self {
+            Some(token) => token.not_expect_roles(roles),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A failed authentication is treated as a token carrying no roles at all.
+impl<R: Role> ExpectRoles<R> for KeycloakAuthStatus<R> {
+    type Rejection = AuthError;
+
+    fn expect_roles<I: Into<R> + Clone>(&self, roles: &[I]) -> Result<(), Self::Rejection> {
+        match self {
+            KeycloakAuthStatus::Success(token) => token.expect_roles(roles),
+            KeycloakAuthStatus::Failure(_) => expect_roles_of_missing_token(roles),
+        }
+    }
+
+    fn not_expect_roles<I: Into<R> + Clone>(&self, roles: &[I]) -> Result<(), Self::Rejection> {
+        match self {
+            KeycloakAuthStatus::Success(token) => token.not_expect_roles(roles),
+            KeycloakAuthStatus::Failure(_) => Ok(()),
+        }
+    }
+}
+
+fn expect_roles_of_missing_token<R: Role, I: Into<R> + Clone>(
+    roles: &[I],
+) -> Result<(), AuthError> {
+    match roles.first() {
+        Some(expected) => Err(AuthError::MissingExpectedRole {
+            role: Into::<R>::into(expected.clone()).to_string(),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// 64 bit FNV-1a. Unlike the hashers of std, its output is guaranteed to be stable.
+fn fnv1a(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    bytes.into_iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, sync::Arc};
+
+    use jsonwebtoken::Algorithm;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use std::str::FromStr;
+
+    use http::{HeaderMap, HeaderValue};
+
+    use crate::{
+        audience::Audience,
+        error::AuthError,
+        permission::PermissionMap,
+        role::{ExpectRoles, KeycloakRole, RoleRequirement},
+        service::test::{claims, create_token, create_token_decoding_key, parse_token},
+        validator::KeycloakTokenValidator,
+        KeycloakAuthStatus, TimestampRangePolicy,
+    };
+
+    use super::{
+        normalize_resource_access, Access, KeycloakToken, LazyRoles, RawClaims, RawToken,
+        RealmAccess, ResourceAccess, StandardClaims, StringOrVecString, ValidationCache,
+        MAX_TOKEN_LEN,
+    };
+
+    #[test]
+    fn summarizes_roles() {
+        let mut claims = claims();
+        claims["resource_access"]["dashboard"] = json!({ "roles": ["viewer", "editor"] });
+        let token = parse_token(claims);
+
+        assert_eq!(token.num_roles(), 4);
+        assert!(token.has_any_role(&["unknown", "editor"]));
+        assert!(!token.has_any_role(&["unknown"]));
+        assert_eq!(
+            token.realm_roles().collect::<Vec<_>>(),
+            vec!["administrator"]
+        );
+        let by_client = token.roles_by_client();
+        assert_eq!(
+            by_client.keys().copied().collect::<Vec<_>>(),
+            vec!["account", "dashboard"]
+        );
+        assert_eq!(by_client["dashboard"], vec!["viewer", "editor"]);
+    }
+
+    #[test]
+    fn parses_session_claims() {
+        let token = parse_token(claims());
+        assert_eq!(token.session(), None);
+        assert_eq!(token.device_id, None);
+        assert_eq!(token.client(), "frontend");
+
+        let mut legacy_claims = claims();
+        legacy_claims["session_state"] = json!("legacy-session");
+        legacy_claims["client_session"] = json!("client-session");
+        legacy_claims["device_id"] = json!("device");
+        let token = parse_token(legacy_claims);
+        assert_eq!(token.session(), Some("legacy-session"));
+        assert_eq!(token.client_session.as_deref(), Some("client-session"));
+        assert_eq!(token.device_id.as_deref(), Some("device"));
+
+        let mut current_claims = claims();
+        current_claims["sid"] = json!("session");
+        assert_eq!(parse_token(current_claims).session(), Some("session"));
+
+        let mut service_account_claims = claims();
+        service_account_claims["client_id"] = json!("billing-service");
+        let token = parse_token(service_account_claims);
+        assert_eq!(token.client(), "billing-service");
+        assert!(token
+            .assert_client_allowed(&[String::from("billing-service")])
+            .is_ok());
+        assert!(matches!(
+            token.assert_client_allowed(&[String::from("frontend")]),
+            Err(AuthError::ClientNotAllowed { client }) if client == "billing-service"
+        ));
+    }
+
+    #[test]
+    fn raw_token_from_str_and_headers() {
+        let token = create_token(claims());
+
+        let raw_token = RawToken::from_str(&token).expect("three segments");
+        assert_eq!(raw_token.as_str(), token);
+        assert_eq!(
+            raw_token.header().expect("valid header").alg,
+            Algorithm::RS256
+        );
+        let raw_claims = raw_token
+            .validate(&KeycloakTokenValidator::new(
+                Arc::new(create_token_decoding_key()),
+                [Audience::try_new("account").expect("non-empty")],
+            ))
+            .expect("valid token");
+        assert_eq!(raw_claims["aud"], json!("account"));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            http::header::AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {token}")).expect("valid header value"),
+        );
+        assert_eq!(
+            RawToken::try_from(&headers).expect("bearer token"),
+            raw_token
+        );
+
+        let mut headers = HeaderMap::new();
+        for (value, expected) in [
+            ("Bearer ", AuthError::EmptyBearerToken),
+            ("Bearer", AuthError::EmptyBearerToken),
+            ("Bearer   ", AuthError::EmptyBearerToken),
+            ("Bearerxyz", AuthError::MissingBearerToken),
+            ("Basic xyz", AuthError::MissingBearerToken),
+        ] {
+            headers.insert(http::header::AUTHORIZATION, HeaderValue::from_static(value));
+            let err = RawToken::try_from(&headers).expect_err("no token");
+            assert_eq!(
+                std::mem::discriminant(&err),
+                std::mem::discriminant(&expected)
+            );
+        }
+
+        for malformed in [
+            String::from("garbage"),
+            String::from("a..c"),
+            String::from("a.b.c+"),
+            format!("{token}.x"),
+            "a".repeat(MAX_TOKEN_LEN) + ".b.c",
+        ] {
+            assert!(matches!(
+                RawToken::from_str(&malformed),
+                Err(AuthError::MalformedToken { .. })
+            ));
+        }
+    }
+
+    #[cfg(feature = "typed-header")]
+    #[test]
+    fn raw_token_from_and_into_typed_header() {
+        use axum::headers::{authorization::Bearer, Authorization};
+
+        let token = create_token(claims());
+        let header = Authorization::bearer(&token).expect("valid bearer token");
+
+        let raw_token =
+            RawToken::try_from(axum::TypedHeader(header.clone())).expect("bearer token");
+        assert_eq!(raw_token.as_str(), token);
+        assert_eq!(
+            Authorization::<Bearer>::try_from(&raw_token).expect("valid header"),
+            header
+        );
+
+        let header = Authorization::bearer("garbage").expect("valid bearer token");
+        assert!(matches!(
+            RawToken::try_from(&header),
+            Err(AuthError::MalformedToken { .. })
+        ));
+    }
+
+    #[test]
+    fn parses_organizations() {
+        let mut claims = claims();
+        claims["organization"] = json!({ "acme": { "roles": ["billing"] } });
+        let token = parse_token(claims);
+
+        assert!(token.is_member_of("acme"));
+        assert!(token.expect_organization("acme").is_ok());
+        assert!(matches!(
+            token.expect_organization("globex"),
+            Err(AuthError::MissingOrganization { .. })
+        ));
+        assert!(token.expect_organization_role("acme", "billing").is_ok());
+        assert!(matches!(
+            token.expect_organization_role("acme", "admin"),
+            Err(AuthError::MissingOrganizationRole { .. })
+        ));
+    }
+
+    #[test]
+    fn summarizes_token_without_pii() {
+        let token = parse_token(claims());
+
+        let summary = token.summary();
+        assert!(summary.starts_with(
+            "sub=f6c4fd0e-4d0f-4a1e-8b3c-2b6f1c8e9d7a, azp=frontend, roles=2, exp in "
+        ));
+        assert!(!summary.contains(&token.email));
+        assert!(!summary.contains(&token.preferred_username));
+    }
+
+    #[test]
+    fn token_clones_share_data() {
+        let token = parse_token(claims());
+
+        let clone = token.clone();
+        assert!(std::ptr::eq(&*token, &*clone));
+
+        // A shared token must be copied, a unique one can be taken apart directly.
+        let parts = clone.into_parts();
+        assert_eq!(parts.subject, token.subject);
+        let rebuilt = KeycloakToken::from_parts(parts);
+        assert_eq!(rebuilt, token);
+        assert_eq!(token.into_parts(), rebuilt.into_parts());
+    }
+
+    #[test]
+    fn token_parts_keep_permissions() {
+        let token = parse_token(claims()).with_permission_map(Arc::new(
+            PermissionMap::default().grant("administrator", ["user:delete"]),
+        ));
+        assert!(token.has_permission("user:delete"));
+
+        let mut parts = token.into_parts();
+        parts.preferred_username = String::from("renamed");
+        let rebuilt = KeycloakToken::from_parts(parts);
+        assert_eq!(rebuilt.preferred_username, "renamed");
+        assert!(rebuilt.has_permission("user:delete"));
+    }
+
+    #[test]
+    fn parses_audiences() {
+        let parse = |aud: Option<serde_json::Value>| {
+            let mut claims = claims();
+            match aud {
+                Some(aud) => claims["aud"] = aud,
+                None => {
+                    claims
+                        .as_object_mut()
+                        .expect("object")
+                        .remove("aud")
+                        .expect("aud");
+                }
+            }
+            let raw_claims = RawClaims::deserialize(claims).expect("valid claims");
+            StandardClaims::parse(raw_claims).expect("parsable standard claims")
+        };
+
+        let single = parse(Some(json!("account")));
+        assert_eq!(
+            single.aud,
+            StringOrVecString::String(String::from("account"))
+        );
+        assert_eq!(single.audiences(), &[String::from("account")]);
+
+        let multiple = parse(Some(json!(["account", "dashboard"])));
+        assert_eq!(
+            multiple.audiences(),
+            &[String::from("account"), String::from("dashboard")]
+        );
+        assert!(multiple.aud.contains("dashboard"));
+        assert!(!multiple.aud.contains("other"));
+
+        for empty in [
+            parse(Some(json!([]))),
+            parse(Some(json!(null))),
+            parse(None),
+        ] {
+            assert!(empty.aud.is_empty());
+            assert!(empty.audiences().is_empty());
+            assert!(!empty.aud.contains(""));
+        }
+
+        let token = KeycloakToken::<String>::parse(multiple, TimestampRangePolicy::Reject)
+            .expect("parsable token");
+        assert_eq!(
+            token.audiences(),
+            &[String::from("account"), String::from("dashboard")]
+        );
+        assert_eq!(
+            token.into_parts().audience.into_vec(),
+            vec![String::from("account"), String::from("dashboard")]
+        );
+    }
+
+    #[test]
+    fn roles_are_extracted_lazily() {
+        let roles = LazyRoles::<String>::new(
+            Some(RealmAccess(Access {
+                roles: vec![String::from("administrator")],
+            })),
+            Some(ResourceAccess(HashMap::from([(
+                String::from("account"),
+                Access {
+                    roles: vec![String::from("manage-account")],
+                },
+            )]))),
+        );
+        assert!(roles.extracted.get().is_none());
+
+        assert_eq!(roles.len(), 2);
+        assert!(roles.extracted.get().is_some());
+        assert_eq!(
+            roles[0],
+            KeycloakRole::Realm {
+                role: String::from("administrator")
+            }
+        );
+        assert_eq!(
+            roles[1],
+            KeycloakRole::Client {
+                client: String::from("account"),
+                role: String::from("manage-account")
+            }
+        );
+    }
+
+    #[test]
+    fn roles_are_compared_as_sets() {
+        let administrator = KeycloakRole::Realm {
+            role: String::from("administrator"),
+        };
+        let manage_account = KeycloakRole::Client {
+            client: String::from("account"),
+            role: String::from("manage-account"),
+        };
+        let extracted = LazyRoles::<String>::new(
+            Some(RealmAccess(Access {
+                roles: vec![String::from("administrator")],
+            })),
+            Some(ResourceAccess(HashMap::from([(
+                String::from("account"),
+                Access {
+                    roles: vec![String::from("manage-account")],
+                },
+            )]))),
+        );
+
+        assert_eq!(
+            extracted,
+            LazyRoles::from(vec![manage_account.clone(), administrator.clone()])
+        );
+        assert_eq!(
+            extracted,
+            LazyRoles::from(vec![
+                administrator.clone(),
+                manage_account.clone(),
+                administrator.clone()
+            ])
+        );
+        assert_ne!(extracted, LazyRoles::from(vec![administrator.clone()]));
+        assert_eq!(Vec::from(extracted), vec![administrator, manage_account]);
+    }
+
+    #[test]
+    fn evaluates_role_requirements() {
+        let token = parse_token(claims());
+
+        let requirement: RoleRequirement = serde_json::from_value(json!({
+            "all_of": [
+                { "realm_role": "administrator" },
+                { "any_of": [
+                    { "client_role": { "client": "billing", "role": "manage-account" } },
+                    { "matching": "manage-*" }
+                ] },
+                { "not": { "role": "banned" } }
+            ]
+        }))
+        .expect("valid requirement");
+        assert!(token.satisfies(&requirement));
+        assert!(token.expect_requirement(&requirement).is_ok());
+
+        assert!(!token.satisfies(&RoleRequirement::RealmRole(String::from("manage-account"))));
+        assert!(!token.satisfies(&RoleRequirement::ClientRole {
+            client: String::from("billing"),
+            role: String::from("manage-account"),
+        }));
+        assert!(matches!(
+            token.expect_requirement(&RoleRequirement::Not(Box::new(RoleRequirement::Role(
+                String::from("administrator")
+            )))),
+            Err(AuthError::MissingExpectedRole { role }) if role == "not(role(administrator))"
+        ));
+    }
+
+    #[test]
+    fn diffs_roles_of_tokens() {
+        let before = parse_token(claims());
+        let mut refreshed_claims = claims();
+        refreshed_claims["realm_access"] = json!({ "roles": ["administrator", "auditor"] });
+        refreshed_claims["resource_access"] = json!({});
+        let after = parse_token(refreshed_claims);
+
+        assert!(before.role_diff(&before).is_empty());
+        let diff = before.role_diff(&after);
+        assert_eq!(
+            diff.added,
+            vec![KeycloakRole::Realm {
+                role: String::from("auditor")
+            }]
+        );
+        assert_eq!(
+            diff.removed,
+            vec![KeycloakRole::Client {
+                client: String::from("account"),
+                role: String::from("manage-account")
+            }]
+        );
+    }
+
+    #[test]
+    fn cache_keys_incorporate_identity() {
+        let token = parse_token(claims());
+        let mut reordered_claims = claims();
+        reordered_claims["realm_access"] = json!({ "roles": ["administrator", "administrator"] });
+        reordered_claims["jti"] = json!("other-token-of-same-user");
+        let mut other_user_claims = claims();
+        other_user_claims["sub"] = json!("other-user");
+        let mut other_roles_claims = claims();
+        other_roles_claims["resource_access"] = json!({ "other": { "roles": ["manage-account"] } });
+
+        let key = token.cache_key("GET /reports");
+        assert!(key.starts_with("GET /reports|"));
+        assert_eq!(key, parse_token(reordered_claims).cache_key("GET /reports"));
+        assert_ne!(
+            key,
+            parse_token(other_user_claims).cache_key("GET /reports")
+        );
+        assert_ne!(
+            key,
+            parse_token(other_roles_claims).cache_key("GET /reports")
+        );
+        assert_eq!(super::fnv1a(*b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn expects_roles_of_optional_tokens() {
+        let token = parse_token(claims());
+
+        assert!(Some(token.clone()).expect_roles(&["administrator"]).is_ok());
+        assert!(None::<KeycloakToken<String>>
+            .expect_roles(&["administrator"])
+            .is_err());
+        assert!(None::<KeycloakToken<String>>
+            .not_expect_roles(&["administrator"])
+            .is_ok());
+        assert!(KeycloakAuthStatus::Success(token)
+            .not_expect_roles(&["administrator"])
+            .is_err());
+        let failure =
+            KeycloakAuthStatus::<String>::Failure(Arc::new(AuthError::MissingAuthorizationHeader));
+        assert!(matches!(
+            failure.expect_roles(&["administrator"]),
+            Err(AuthError::MissingExpectedRole { role }) if role == "administrator"
+        ));
+        assert!(failure.expect_roles::<String>(&[]).is_ok());
+    }
+
+    #[test]
+    fn role_checks_are_memoized() {
+        let roles = LazyRoles::<String>::new(
+            Some(RealmAccess(Access {
+                roles: (0..500).map(|i| format!("role-{i}")).collect(),
+            })),
+            Some(ResourceAccess(HashMap::from([(
+                String::from("account"),
+                Access {
+                    roles: vec![String::from("role-0")],
+                },
+            )]))),
+        );
+        assert!(roles.set.get().is_none());
+
+        assert!(roles.contains(&String::from("role-499")));
+        assert!(!roles.contains(&String::from("role-500")));
+        assert!(roles.set.get().is_some());
+        assert_eq!(roles.len(), 501);
+        assert_eq!(roles.as_set().len(), 500);
+    }
+
+    #[test]
+    fn validation_is_reused_per_algorithm() {
+        let validations = ValidationCache::new(&[Audience::try_new("account").expect("non-empty")]);
+
+        let rs256 = validations.get(Algorithm::RS256);
+        assert!(Arc::ptr_eq(&rs256, &validations.get(Algorithm::RS256)));
+        assert!(!Arc::ptr_eq(&rs256, &validations.get(Algorithm::ES256)));
+        assert_eq!(rs256.algorithms, vec![Algorithm::RS256]);
+        assert!(rs256
+            .aud
+            .as_ref()
+            .is_some_and(|aud| aud.contains("account")));
+    }
+
+    #[test]
+    fn normalizes_known_resource_access_layouts() {
+        let expected = json!({ "billing": { "roles": ["viewer", "payer"] } });
+        for resource_access in [
+            json!([{ "client": "billing", "roles": ["viewer", "payer"] }]),
+            json!([{ "clientId": "billing", "roles": [{ "role": "viewer" }, { "role": "payer" }] }]),
+            json!({ "billing": ["viewer", "payer"] }),
+            json!({ "billing": { "roles": [{ "name": "viewer" }, { "name": "payer" }] } }),
+            expected.clone(),
+        ] {
+            let mut raw_claims =
+                RawClaims::from([(String::from("resource_access"), resource_access)]);
+            normalize_resource_access(&mut raw_claims);
+            assert_eq!(raw_claims["resource_access"], expected);
+        }
+
+        let unknown = json!({ "billing": { "permissions": ["viewer"] } });
+        let mut raw_claims = RawClaims::from([(String::from("resource_access"), unknown.clone())]);
+        normalize_resource_access(&mut raw_claims);
+        assert_eq!(raw_claims["resource_access"], unknown);
+    }
+}
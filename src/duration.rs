@@ -0,0 +1,96 @@
+//! (De)serialization of `Duration`s in a human-readable format, e.g. `"5m"`, `"30s"` or `"1h 30m"`.
+//! Plain numbers are accepted as seconds.
+//!
+//! Use it with `#[serde(with = "axum_keycloak_auth::duration")]`, or `axum_keycloak_auth::duration::option` for `Option<Duration>`.
+
+use std::time::Duration;
+
+use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HumanOrSeconds {
+    Human(String),
+    Seconds(u64),
+}
+
+impl HumanOrSeconds {
+    fn into_duration<E: Error>(self) -> Result<Duration, E> {
+        match self {
+            HumanOrSeconds::Human(human) => humantime::parse_duration(&human).map_err(E::custom),
+            HumanOrSeconds::Seconds(seconds) => Ok(Duration::from_secs(seconds)),
+        }
+    }
+}
+
+pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&humantime::format_duration(*duration))
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    HumanOrSeconds::deserialize(deserializer)?.into_duration()
+}
+
+pub mod option {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::HumanOrSeconds;
+
+    pub fn serialize<S: Serializer>(
+        duration: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match duration {
+            Some(duration) => super::serialize(duration, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Option::<HumanOrSeconds>::deserialize(deserializer)?
+            .map(HumanOrSeconds::into_duration)
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Config {
+        #[serde(with = "crate::duration")]
+        interval: Duration,
+        #[serde(default, with = "crate::duration::option")]
+        leeway: Option<Duration>,
+    }
+
+    #[test]
+    fn parses_human_readable_durations() {
+        let config: Config = serde_json::from_value(json!({ "interval": "1h 30m", "leeway": 30 }))
+            .expect("valid config");
+        assert_eq!(
+            config,
+            Config {
+                interval: Duration::from_secs(90 * 60),
+                leeway: Some(Duration::from_secs(30)),
+            }
+        );
+        assert_eq!(
+            serde_json::to_value(&config).expect("serializable"),
+            json!({ "interval": "1h 30m", "leeway": "30s" })
+        );
+
+        let config: Config =
+            serde_json::from_value(json!({ "interval": "5m" })).expect("valid config");
+        assert_eq!(config.leeway, None);
+        assert!(serde_json::from_value::<Config>(json!({ "interval": "5 parsecs" })).is_err());
+    }
+}
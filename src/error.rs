@@ -1,11 +1,11 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, path::PathBuf, time::Duration};
 
 use axum::{
-    http::StatusCode,
+    http::{header::RETRY_AFTER, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
-use serde_json::json;
+use serde::Serialize;
 use snafu::Snafu;
 
 #[derive(Debug, Snafu)]
@@ -15,6 +15,19 @@ pub enum AuthError {
     #[snafu(display("The 'Authorization' header was not present on a request."))]
     MissingAuthorizationHeader,
 
+    /// The ID token header configured as `id_token_header` was not present on a request, although it is required.
+    #[snafu(display("The ID token header was not present on a request."))]
+    MissingIdToken,
+
+    /// The ID token sent alongside the access token was invalid.
+    #[snafu(display("The ID token is invalid. Source: {source}"))]
+    InvalidIdToken { source: Box<AuthError> },
+
+    /// The ID token sent alongside the access token belongs to a different login than the access token,
+    /// as the given claim does not match.
+    #[snafu(display("The ID token does not match the access token (claim '{claim}')."))]
+    IdTokenMismatch { claim: String },
+
     /// The 'Authorization' header was present on a request but its value could not be parsed.
     /// This can occur if the header value did not solely contain visible ASCII characters.
     #[snafu(display("The 'Authorization' header was present on a request but its value could not be parsed. Reason: {reason}"))]
@@ -26,12 +39,95 @@ pub enum AuthError {
     ))]
     MissingBearerToken,
 
+    /// The 'Authorization' header contained the "Bearer" scheme, but no token.
+    /// Some SDKs send exactly this when they have no token stored.
+    #[snafu(display("The 'Authorization' header did not contain a token after 'Bearer'."))]
+    EmptyBearerToken,
+
     /// The DecodingKey, required for decoding tokens, could not be created.
     #[snafu(display(
         "The DecodingKey, required for decoding tokens, could not be created. Source: {source}"
     ))]
     CreateDecodingKey { source: jsonwebtoken::errors::Error },
 
+    /// Neither a `decoding_key` nor a Keycloak `instance` was configured on the `KeycloakAuthLayer`.
+    #[snafu(display("No DecodingKey or KeycloakAuthInstance is configured."))]
+    NoDecodingKey,
+
+    /// A request to Keycloak (e.g. fetching the discovery document or the realm's keys) failed.
+    #[snafu(display("The request to Keycloak ({url}) failed. Source: {source}"))]
+    KeycloakRequest { url: String, source: reqwest::Error },
+
+    /// The default HTTP client for requests to Keycloak could not be built as configured in the `KeycloakConfig`.
+    #[snafu(display(
+        "The HTTP client for requests to Keycloak could not be built. Reason: {reason}"
+    ))]
+    BuildHttpClient { reason: String },
+
+    /// The JWKS file configured as `KeycloakKeySource::JwksFile` could not be read.
+    #[snafu(display("The JWKS file '{}' could not be read. Source: {source}", path.display()))]
+    ReadJwksFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    /// The JWKS file configured as `KeycloakKeySource::JwksFile` is not a valid JSON Web Key Set.
+    #[snafu(display("The JWKS file '{}' is not a valid JSON Web Key Set. Source: {source}", path.display()))]
+    ParseJwksFile {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+
+    /// The JWKS file configured as `KeycloakKeySource::JwksFile` could not be watched for changes.
+    #[snafu(display("The JWKS file '{}' could not be watched for changes: {reason}", path.display()))]
+    WatchJwksFile { path: PathBuf, reason: String },
+
+    /// The snapshot configured as `KeycloakConfig::snapshot` could not be read.
+    #[snafu(display("The snapshot '{}' could not be read. Source: {source}", path.display()))]
+    ReadSnapshot {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    /// The snapshot configured as `KeycloakConfig::snapshot` is not a valid `KeycloakSnapshot`.
+    #[snafu(display("The snapshot '{}' is not a valid snapshot of a Keycloak realm. Source: {source}", path.display()))]
+    ParseSnapshot {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+
+    /// A `KeycloakSnapshot` could not be written.
+    #[snafu(display("The snapshot '{}' could not be written. Source: {source}", path.display()))]
+    WriteSnapshot {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    /// The realm's keys were not refreshed, as they were refreshed less than `min_key_refresh_interval` ago.
+    #[snafu(display("The keys of the realm were refreshed recently. Retry in {retry_in:?}."))]
+    KeyRefreshSuppressed { retry_in: Duration },
+
+    /// None of the keys of the Keycloak realm matches the key ID ('kid' header) of the token.
+    #[snafu(display("No key of the realm matches the key ID of the token."))]
+    UnknownKeyId { kid: Option<String> },
+
+    /// The token was signed with a key the realm rotated out longer than `KeycloakConfig::retired_key_grace_period` ago,
+    /// even though the key is still published.
+    #[snafu(display("The token was signed with a key retired longer than the grace period ago."))]
+    RetiredKey { kid: Option<String> },
+
+    /// The token was signed using an algorithm ('alg' header) not among the layer's `allowed_algorithms`.
+    #[snafu(display("The token's signing algorithm {alg:?} is not allowed."))]
+    DisallowedAlgorithm { alg: jsonwebtoken::Algorithm },
+
+    /// An expected audience was configured as an empty string, which would never match any token.
+    #[snafu(display("An expected audience must not be empty."))]
+    EmptyAudience,
+
+    /// The token did not have the structural shape of a JWT and was rejected before being decoded.
+    #[snafu(display("The token is malformed. Reason: {reason}"))]
+    MalformedToken { reason: String },
+
     /// The JWT header could not be decoded.
     #[snafu(display("The JWT header could not be decoded. Source: {source}"))]
     DecodeHeader { source: jsonwebtoken::errors::Error },
@@ -40,45 +136,507 @@ pub enum AuthError {
     #[snafu(display("The JWT could not be decoded. Source: {source}"))]
     Decode { source: jsonwebtoken::errors::Error },
 
+    /// The JWT was not issued for any of the expected audiences.
+    /// Most likely, the client scope used to issue the token lacks an audience mapper in Keycloak.
+    /// The audiences of the token are logged on debug level.
+    #[snafu(display("The JWT was not issued for any expected audience. Check the audience mapper of the client scope in Keycloak."))]
+    WrongAudience { source: jsonwebtoken::errors::Error },
+
+    /// The token was not issued by the expected issuer, e.g. by another realm.
+    #[snafu(display("The JWT was not issued by the expected issuer."))]
+    WrongIssuer { source: jsonwebtoken::errors::Error },
+
+    /// Too many tokens are currently being validated. The client should retry after the given duration.
+    #[snafu(display("Too many requests are currently being authenticated. Retry later."))]
+    Overloaded { retry_after: Duration },
+
+    /// Keycloak is considered unreachable, as the circuit breaker guarding requests to it is open (see `CircuitBreakerPolicy`).
+    /// The client should retry after the given duration.
+    #[snafu(display("Keycloak is currently unreachable. Retry later."))]
+    UpstreamUnavailable { retry_after: Duration },
+
+    /// The JWT was handed to a blocking task for verification, but that task did not complete.
+    #[snafu(display("The JWT verification task did not complete. Source: {source}"))]
+    VerificationTask { source: tokio::task::JoinError },
+
     /// Parts of the JWT could not be parsed.
     #[snafu(display("Parts of the JWT could not be parsed. Source: {source}"))]
     JsonParse { source: serde_json::Error },
 
+    /// A claim configured as required was not present in the JWT.
+    /// This most likely hints at a misconfigured client scope or mapper in Keycloak.
+    #[snafu(display("The required claim '{claim}' was not present in the JWT."))]
+    MissingRequiredClaim { claim: String },
+
+    /// The claims of the JWT did not match the configured `ClaimsSchema`.
+    /// Note: The `IntoResponse` implementation will only show the violation in a debug build!
+    #[snafu(display("The claims of the JWT do not match the expected schema: {reason}"))]
+    ClaimsSchemaViolation { reason: String },
+
+    /// The provided JSON Schema could not be compiled.
+    #[snafu(display("The claims schema is not a valid JSON Schema: {reason}"))]
+    InvalidClaimsSchema { reason: String },
+
+    /// The JWT ID ('jti' claim) of the token is not in the configured `JtiFormat`.
+    #[snafu(display("The JWT ID ('jti' claim) '{jti}' is not in the expected format."))]
+    InvalidJti { jti: String },
+
     /// The tokens lifetime is expired.
     #[snafu(display("The tokens lifetime is expired."))]
     TokenExpired,
 
+    /// A timestamp claim of the token ('exp' or 'iat') lies beyond the range of representable points in time.
+    /// See `TimestampRangePolicy`.
+    #[snafu(display(
+        "The '{claim}' claim ({timestamp}) is out of the range of representable timestamps."
+    ))]
+    TimestampOutOfRange { claim: String, timestamp: i64 },
+
+    /// The token was issued (JWT 'iat' claim) further in the future than the configured leeway allows,
+    /// hinting at clock problems of the issuer (or this server) or a forged token.
+    #[snafu(display("The token was issued {skew:?} in the future."))]
+    TokenIssuedInFuture { skew: Duration },
+
+    /// The token is of a type (JWT 'typ' claim) not accepted by the layer.
+    #[snafu(display("Tokens of type '{typ}' are not accepted."))]
+    UnexpectedTokenType { typ: String },
+
+    /// The token was already used before, on a route only accepting each token once.
+    #[snafu(display("The token was already used."))]
+    TokenReplayed,
+
+    /// The token restricts the IPs it may be used from, and the request's source IP is not among them or could not be determined.
+    #[snafu(display("The token may not be used from this IP address."))]
+    SourceIpNotAllowed,
+
+    /// Note: The `IntoResponse` implementation will only show the provided client in a debug build!
+    #[snafu(display(
+        "The token was issued to a client (omitted for security reasons) which is not allowed."
+    ))]
+    ClientNotAllowed { client: String },
+
     /// For a not further known reason, the token was deemed invalid
     #[snafu(display(
         "For a not further known reason, the token was deemed invalid: Reason: {reason}"
     ))]
     InvalidToken { reason: String },
 
+    /// An extractor was used on a route not protected by a `KeycloakAuthLayer` (of the same role type).
+    /// This is a developer error, not the fault of the client.
+    #[snafu(display("No KeycloakAuthLayer is installed for this route."))]
+    LayerNotInstalled,
+
+    /// No successfully authenticated token was found for the request.
+    /// Returned by the extractors when the authentication failed in `PassthroughMode::Pass`.
+    #[snafu(display("The request was not authenticated."))]
+    NotAuthenticated,
+
+    /// The token carried neither a 'realm_access' nor a 'resource_access' claim, which was configured to be rejected.
+    #[snafu(display("The token did not contain any roles."))]
+    MissingRoles,
+
     /// Note: The `IntoResponse` implementation will only show the provided role in a debug build!
     #[snafu(display("An expected role (omitted for security reasons) was missing."))]
     MissingExpectedRole { role: String },
 
+    /// Note: The `IntoResponse` implementation will only show the provided permission in a debug build!
+    #[snafu(display("An expected permission (omitted for security reasons) was missing."))]
+    MissingPermission { permission: String },
+
+    /// Note: The `IntoResponse` implementation will only show the provided requirement in a debug build!
+    #[snafu(display("An authorization requirement (omitted for security reasons) was not met."))]
+    UnmetRequirement { requirement: String },
+
+    /// Note: The `IntoResponse` implementation will only show the provided organization in a debug build!
+    #[snafu(display(
+        "The user is not a member of an expected organization (omitted for security reasons)."
+    ))]
+    MissingOrganization { organization: String },
+
+    /// Note: The `IntoResponse` implementation will only show the provided organization and role in a debug build!
+    #[snafu(display(
+        "The user lacks an expected role in an organization (omitted for security reasons)."
+    ))]
+    MissingOrganizationRole { organization: String, role: String },
+
+    /// Note: The `IntoResponse` implementation will only show the provided restriction in a debug build!
+    #[snafu(display("A claim restriction (omitted for security reasons) was not met."))]
+    ClaimRestrictionViolated { restriction: String },
+
     /// An unexpected role was present.
     #[snafu(display("An unexpected role was present."))]
     UnexpectedRole,
 }
 
-impl IntoResponse for AuthError {
-    fn into_response(self) -> Response {
-        let (status, error_message) = match self {
+/// Coarse classification of why a token could not be decoded and validated.
+/// Allows telling attacks (bad signatures) apart from client bugs (malformed or expired tokens)
+/// and misconfigurations (wrong audience or issuer, unknown keys).
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum DecodeFailureCategory {
+    /// The token was structurally invalid and could not be parsed.
+    Malformed,
+    /// The token's signature did not match its content.
+    BadSignature,
+    /// The token's lifetime is expired.
+    Expired,
+    /// The token was not issued for any of the expected audiences.
+    WrongAudience,
+    /// The token was not issued by the expected issuer.
+    WrongIssuer,
+    /// The token was signed with a key (or algorithm) not matching any known decoding key.
+    UnknownKey,
+    /// Any other reason.
+    Other,
+}
+
+impl DecodeFailureCategory {
+    /// A stable, lowercase name of this category, usable as a metrics label.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DecodeFailureCategory::Malformed => "malformed",
+            DecodeFailureCategory::BadSignature => "bad_signature",
+            DecodeFailureCategory::Expired => "expired",
+            DecodeFailureCategory::WrongAudience => "wrong_audience",
+            DecodeFailureCategory::WrongIssuer => "wrong_issuer",
+            DecodeFailureCategory::UnknownKey => "unknown_key",
+            DecodeFailureCategory::Other => "other",
+        }
+    }
+
+    /// How suspicious a failure of this category is, see `Severity`.
+    pub fn severity(&self) -> Severity {
+        match self {
+            DecodeFailureCategory::Expired => Severity::Info,
+            DecodeFailureCategory::Malformed
+            | DecodeFailureCategory::WrongAudience
+            | DecodeFailureCategory::UnknownKey
+            | DecodeFailureCategory::Other => Severity::Warning,
+            DecodeFailureCategory::BadSignature | DecodeFailureCategory::WrongIssuer => {
+                Severity::Critical
+            }
+        }
+    }
+
+    fn from_jwt_error(err: &jsonwebtoken::errors::Error) -> Self {
+        use jsonwebtoken::errors::ErrorKind;
+        match err.kind() {
+            ErrorKind::InvalidToken
+            | ErrorKind::InvalidAlgorithmName
+            | ErrorKind::MissingAlgorithm
+            | ErrorKind::MissingRequiredClaim(_)
+            | ErrorKind::Base64(_)
+            | ErrorKind::Json(_)
+            | ErrorKind::Utf8(_) => DecodeFailureCategory::Malformed,
+            ErrorKind::InvalidSignature => DecodeFailureCategory::BadSignature,
+            ErrorKind::ExpiredSignature => DecodeFailureCategory::Expired,
+            ErrorKind::InvalidAudience => DecodeFailureCategory::WrongAudience,
+            ErrorKind::InvalidIssuer => DecodeFailureCategory::WrongIssuer,
+            ErrorKind::InvalidAlgorithm
+            | ErrorKind::InvalidRsaKey(_)
+            | ErrorKind::InvalidEcdsaKey
+            | ErrorKind::InvalidKeyFormat => DecodeFailureCategory::UnknownKey,
+            _ => DecodeFailureCategory::Other,
+        }
+    }
+}
+
+impl std::fmt::Display for DecodeFailureCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// How suspicious a rejected token is, allowing alerting to prioritize genuinely suspicious events.
+/// Ordered from least to most severe.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum Severity {
+    /// Expected in normal operation, e.g. an expired token a client is about to refresh.
+    Info,
+    /// Likely a client bug or misconfiguration, e.g. a token issued for another audience.
+    Warning,
+    /// Likely an attack, e.g. a forged signature or a token of an unknown issuer.
+    Critical,
+}
+
+impl Severity {
+    /// A stable, lowercase name of this severity, usable as a metrics label.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl AuthError {
+    /// Classifies this error if it stems from decoding or validating the token itself.
+    /// Returns `None` for errors unrelated to the token's content, e.g. a missing 'Authorization' header or a missing role.
+    pub fn decode_failure_category(&self) -> Option<DecodeFailureCategory> {
+        match self {
+            AuthError::MalformedToken { reason: _ } => Some(DecodeFailureCategory::Malformed),
+            AuthError::InvalidIdToken { source } => source.decode_failure_category(),
+            AuthError::IdTokenMismatch { claim: _ } => Some(DecodeFailureCategory::Other),
+            AuthError::DecodeHeader { source: _ } => Some(DecodeFailureCategory::Malformed),
+            AuthError::Decode { source } => Some(DecodeFailureCategory::from_jwt_error(source)),
+            AuthError::WrongAudience { source: _ } => Some(DecodeFailureCategory::WrongAudience),
+            AuthError::WrongIssuer { source: _ } => Some(DecodeFailureCategory::WrongIssuer),
+            AuthError::JsonParse { source: _ } => Some(DecodeFailureCategory::Malformed),
+            AuthError::MissingRequiredClaim { claim: _ } => Some(DecodeFailureCategory::Malformed),
+            AuthError::InvalidJti { jti: _ } => Some(DecodeFailureCategory::Malformed),
+            AuthError::ClaimsSchemaViolation { reason: _ } => {
+                Some(DecodeFailureCategory::Malformed)
+            }
+            AuthError::TokenExpired => Some(DecodeFailureCategory::Expired),
+            AuthError::UnknownKeyId { kid: _ } => Some(DecodeFailureCategory::UnknownKey),
+            AuthError::RetiredKey { kid: _ } => Some(DecodeFailureCategory::UnknownKey),
+            AuthError::DisallowedAlgorithm { alg: _ } => Some(DecodeFailureCategory::BadSignature),
+            AuthError::TokenIssuedInFuture { skew: _ } => Some(DecodeFailureCategory::Other),
+            AuthError::TimestampOutOfRange {
+                claim: _,
+                timestamp: _,
+            } => Some(DecodeFailureCategory::Malformed),
+            AuthError::UnexpectedTokenType { typ: _ } => Some(DecodeFailureCategory::Other),
+            AuthError::TokenReplayed => Some(DecodeFailureCategory::Other),
+            AuthError::InvalidToken { reason: _ } => Some(DecodeFailureCategory::Other),
+            AuthError::MissingAuthorizationHeader
+            | AuthError::MissingIdToken
+            | AuthError::InvalidAuthorizationHeader { reason: _ }
+            | AuthError::MissingBearerToken
+            | AuthError::EmptyBearerToken
+            | AuthError::CreateDecodingKey { source: _ }
+            | AuthError::NoDecodingKey
+            | AuthError::KeycloakRequest { url: _, source: _ }
+            | AuthError::BuildHttpClient { reason: _ }
+            | AuthError::ReadJwksFile { path: _, source: _ }
+            | AuthError::ParseJwksFile { path: _, source: _ }
+            | AuthError::WatchJwksFile { path: _, reason: _ }
+            | AuthError::ReadSnapshot { path: _, source: _ }
+            | AuthError::ParseSnapshot { path: _, source: _ }
+            | AuthError::WriteSnapshot { path: _, source: _ }
+            | AuthError::KeyRefreshSuppressed { retry_in: _ }
+            | AuthError::InvalidClaimsSchema { reason: _ }
+            | AuthError::EmptyAudience
+            | AuthError::Overloaded { retry_after: _ }
+            | AuthError::UpstreamUnavailable { retry_after: _ }
+            | AuthError::VerificationTask { source: _ }
+            | AuthError::LayerNotInstalled
+            | AuthError::NotAuthenticated
+            | AuthError::MissingRoles
+            | AuthError::SourceIpNotAllowed
+            | AuthError::ClientNotAllowed { client: _ }
+            | AuthError::MissingExpectedRole { role: _ }
+            | AuthError::MissingPermission { permission: _ }
+            | AuthError::UnmetRequirement { requirement: _ }
+            | AuthError::MissingOrganization { organization: _ }
+            | AuthError::MissingOrganizationRole {
+                organization: _,
+                role: _,
+            }
+            | AuthError::ClaimRestrictionViolated { restriction: _ }
+            | AuthError::UnexpectedRole => None,
+        }
+    }
+
+    /// A stable, machine-readable code identifying the kind of this error (e.g. "token_expired"),
+    /// usable by clients to react to specific failures, as opposed to the human-readable message.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AuthError::MalformedToken { reason: _ } => "malformed_token",
+            AuthError::InvalidIdToken { source: _ } => "invalid_id_token",
+            AuthError::IdTokenMismatch { claim: _ } => "id_token_mismatch",
+            AuthError::DecodeHeader { source: _ } => "decode_header",
+            AuthError::Decode { source: _ } => "decode",
+            AuthError::WrongAudience { source: _ } => "wrong_audience",
+            AuthError::WrongIssuer { source: _ } => "wrong_issuer",
+            AuthError::JsonParse { source: _ } => "json_parse",
+            AuthError::MissingRequiredClaim { claim: _ } => "missing_required_claim",
+            AuthError::InvalidJti { jti: _ } => "invalid_jti",
+            AuthError::ClaimsSchemaViolation { reason: _ } => "claims_schema_violation",
+            AuthError::TokenExpired => "token_expired",
+            AuthError::UnknownKeyId { kid: _ } => "unknown_key_id",
+            AuthError::RetiredKey { kid: _ } => "retired_key",
+            AuthError::DisallowedAlgorithm { alg: _ } => "disallowed_algorithm",
+            AuthError::TokenIssuedInFuture { skew: _ } => "token_issued_in_future",
+            AuthError::TimestampOutOfRange {
+                claim: _,
+                timestamp: _,
+            } => "timestamp_out_of_range",
+            AuthError::UnexpectedTokenType { typ: _ } => "unexpected_token_type",
+            AuthError::TokenReplayed => "token_replayed",
+            AuthError::InvalidToken { reason: _ } => "invalid_token",
+            AuthError::MissingAuthorizationHeader => "missing_authorization_header",
+            AuthError::MissingIdToken => "missing_id_token",
+            AuthError::InvalidAuthorizationHeader { reason: _ } => "invalid_authorization_header",
+            AuthError::MissingBearerToken => "missing_bearer_token",
+            AuthError::EmptyBearerToken => "empty_bearer_token",
+            AuthError::CreateDecodingKey { source: _ } => "create_decoding_key",
+            AuthError::NoDecodingKey => "no_decoding_key",
+            AuthError::KeycloakRequest { url: _, source: _ } => "keycloak_request",
+            AuthError::BuildHttpClient { reason: _ } => "build_http_client",
+            AuthError::ReadJwksFile { path: _, source: _ } => "read_jwks_file",
+            AuthError::ParseJwksFile { path: _, source: _ } => "parse_jwks_file",
+            AuthError::WatchJwksFile { path: _, reason: _ } => "watch_jwks_file",
+            AuthError::ReadSnapshot { path: _, source: _ } => "read_snapshot",
+            AuthError::ParseSnapshot { path: _, source: _ } => "parse_snapshot",
+            AuthError::WriteSnapshot { path: _, source: _ } => "write_snapshot",
+            AuthError::KeyRefreshSuppressed { retry_in: _ } => "key_refresh_suppressed",
+            AuthError::InvalidClaimsSchema { reason: _ } => "invalid_claims_schema",
+            AuthError::EmptyAudience => "empty_audience",
+            AuthError::Overloaded { retry_after: _ } => "overloaded",
+            AuthError::UpstreamUnavailable { retry_after: _ } => "upstream_unavailable",
+            AuthError::VerificationTask { source: _ } => "verification_task",
+            AuthError::LayerNotInstalled => "layer_not_installed",
+            AuthError::NotAuthenticated => "not_authenticated",
+            AuthError::MissingRoles => "missing_roles",
+            AuthError::SourceIpNotAllowed => "source_ip_not_allowed",
+            AuthError::ClientNotAllowed { client: _ } => "client_not_allowed",
+            AuthError::MissingExpectedRole { role: _ } => "missing_expected_role",
+            AuthError::MissingPermission { permission: _ } => "missing_permission",
+            AuthError::UnmetRequirement { requirement: _ } => "unmet_requirement",
+            AuthError::MissingOrganization { organization: _ } => "missing_organization",
+            AuthError::MissingOrganizationRole {
+                organization: _,
+                role: _,
+            } => "missing_organization_role",
+            AuthError::ClaimRestrictionViolated { restriction: _ } => "claim_restriction_violated",
+            AuthError::UnexpectedRole => "unexpected_role",
+        }
+    }
+}
+
+/// The JSON body of error responses. Implement this for a serializable type to respond with a company-standard envelope,
+/// and set `AuthError::into_response_with::<YourBody>` as the `error_responder` of the `KeycloakAuthLayer`.
+pub trait ErrorBody: Serialize {
+    /// Creates the body for `error`, which is answered with `status`.
+    /// `message` is the message `DefaultErrorBody` would contain, with sensitive details already omitted in release builds.
+    fn from_error(error: &AuthError, status: StatusCode, message: &str) -> Self;
+}
+
+/// The default error body: `{ "error": "message" }`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DefaultErrorBody {
+    pub error: String,
+}
+
+impl ErrorBody for DefaultErrorBody {
+    fn from_error(_error: &AuthError, _status: StatusCode, message: &str) -> Self {
+        Self {
+            error: message.to_owned(),
+        }
+    }
+}
+
+impl AuthError {
+    /// Creates the response to this error, with a body of type `B`.
+    pub fn into_response_with<B: ErrorBody>(self) -> Response {
+        let (status, error_message) = self.status_and_message();
+        let body = Json(B::from_error(&self, status, &error_message));
+        let mut response = (status, body).into_response();
+        if let AuthError::Overloaded { retry_after }
+        | AuthError::UpstreamUnavailable { retry_after } = self
+        {
+            // Whole seconds, rounded up, as required by the header.
+            let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(seconds));
+        }
+        response
+    }
+
+    pub(crate) fn status_and_message(&self) -> (StatusCode, Cow<'static, str>) {
+        match self {
             err @ AuthError::MissingAuthorizationHeader => {
                 (StatusCode::BAD_REQUEST, Cow::Owned(err.to_string()))
             }
+            err @ AuthError::MissingIdToken => {
+                (StatusCode::BAD_REQUEST, Cow::Owned(err.to_string()))
+            }
+            AuthError::InvalidIdToken { source } => {
+                let (status, message) = source.status_and_message();
+                (
+                    status,
+                    Cow::Owned(format!("The ID token is invalid: {message}")),
+                )
+            }
+            err @ AuthError::IdTokenMismatch { claim: _ } => {
+                (StatusCode::UNAUTHORIZED, Cow::Owned(err.to_string()))
+            }
             err @ AuthError::InvalidAuthorizationHeader { reason: _ } => {
                 (StatusCode::BAD_REQUEST, Cow::Owned(err.to_string()))
             }
             err @ AuthError::MissingBearerToken => {
                 (StatusCode::BAD_REQUEST, Cow::Owned(err.to_string()))
             }
+            err @ AuthError::EmptyBearerToken => {
+                (StatusCode::BAD_REQUEST, Cow::Owned(err.to_string()))
+            }
             err @ AuthError::CreateDecodingKey { source: _ } => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Cow::Owned(err.to_string()),
             ),
+            err @ AuthError::NoDecodingKey => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Cow::Owned(err.to_string()),
+            ),
+            err @ AuthError::KeycloakRequest { url: _, source: _ } => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Cow::Owned(err.to_string()),
+            ),
+            err @ AuthError::BuildHttpClient { reason: _ } => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Cow::Owned(err.to_string()),
+            ),
+            err @ AuthError::ReadJwksFile { path: _, source: _ } => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Cow::Owned(err.to_string()),
+            ),
+            err @ AuthError::ParseJwksFile { path: _, source: _ } => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Cow::Owned(err.to_string()),
+            ),
+            err @ AuthError::WatchJwksFile { path: _, reason: _ } => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Cow::Owned(err.to_string()),
+            ),
+            err @ AuthError::ReadSnapshot { path: _, source: _ } => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Cow::Owned(err.to_string()),
+            ),
+            err @ AuthError::ParseSnapshot { path: _, source: _ } => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Cow::Owned(err.to_string()),
+            ),
+            err @ AuthError::WriteSnapshot { path: _, source: _ } => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Cow::Owned(err.to_string()),
+            ),
+            err @ AuthError::KeyRefreshSuppressed { retry_in: _ } => {
+                (StatusCode::SERVICE_UNAVAILABLE, Cow::Owned(err.to_string()))
+            }
+            err @ AuthError::UnknownKeyId { kid: _ } => {
+                (StatusCode::UNAUTHORIZED, Cow::Owned(err.to_string()))
+            }
+            err @ AuthError::RetiredKey { kid: _ } => {
+                (StatusCode::UNAUTHORIZED, Cow::Owned(err.to_string()))
+            }
+            err @ AuthError::DisallowedAlgorithm { alg: _ } => {
+                (StatusCode::UNAUTHORIZED, Cow::Owned(err.to_string()))
+            }
+            err @ AuthError::EmptyAudience => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Cow::Owned(err.to_string()),
+            ),
+            err @ AuthError::MalformedToken { reason: _ } => {
+                (StatusCode::BAD_REQUEST, Cow::Owned(err.to_string()))
+            }
             err @ AuthError::DecodeHeader { source: _ } => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Cow::Owned(err.to_string()),
@@ -87,16 +645,86 @@ impl IntoResponse for AuthError {
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Cow::Owned(err.to_string()),
             ),
+            err @ AuthError::WrongAudience { source: _ } => {
+                (StatusCode::UNAUTHORIZED, Cow::Owned(err.to_string()))
+            }
+            err @ AuthError::WrongIssuer { source: _ } => {
+                (StatusCode::UNAUTHORIZED, Cow::Owned(err.to_string()))
+            }
+            err @ AuthError::Overloaded { retry_after: _ } => {
+                (StatusCode::SERVICE_UNAVAILABLE, Cow::Owned(err.to_string()))
+            }
+            err @ AuthError::UpstreamUnavailable { retry_after: _ } => {
+                (StatusCode::SERVICE_UNAVAILABLE, Cow::Owned(err.to_string()))
+            }
+            err @ AuthError::VerificationTask { source: _ } => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Cow::Owned(err.to_string()),
+            ),
             err @ AuthError::JsonParse { source: _ } => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Cow::Owned(err.to_string()),
             ),
+            err @ AuthError::MissingRequiredClaim { claim: _ } => {
+                (StatusCode::BAD_REQUEST, Cow::Owned(err.to_string()))
+            }
+            AuthError::ClaimsSchemaViolation { reason } => (
+                StatusCode::BAD_REQUEST,
+                match cfg!(debug_assertions) {
+                    true => Cow::Owned(format!(
+                        "The claims of the JWT do not match the expected schema: {reason}"
+                    )),
+                    false => {
+                        Cow::Borrowed("The claims of the JWT do not match the expected schema.")
+                    }
+                },
+            ),
+            err @ AuthError::InvalidClaimsSchema { reason: _ } => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Cow::Owned(err.to_string()),
+            ),
+            err @ AuthError::InvalidJti { jti: _ } => {
+                (StatusCode::UNAUTHORIZED, Cow::Owned(err.to_string()))
+            }
             err @ AuthError::TokenExpired => {
                 (StatusCode::UNAUTHORIZED, Cow::Owned(err.to_string()))
             }
+            err @ AuthError::TimestampOutOfRange {
+                claim: _,
+                timestamp: _,
+            } => (StatusCode::UNAUTHORIZED, Cow::Owned(err.to_string())),
+            err @ AuthError::TokenIssuedInFuture { skew: _ } => {
+                (StatusCode::UNAUTHORIZED, Cow::Owned(err.to_string()))
+            }
+            err @ AuthError::UnexpectedTokenType { typ: _ } => {
+                (StatusCode::UNAUTHORIZED, Cow::Owned(err.to_string()))
+            }
+            err @ AuthError::TokenReplayed => {
+                (StatusCode::UNAUTHORIZED, Cow::Owned(err.to_string()))
+            }
+            err @ AuthError::SourceIpNotAllowed => {
+                (StatusCode::UNAUTHORIZED, Cow::Owned(err.to_string()))
+            }
+            AuthError::ClientNotAllowed { client } => (
+                StatusCode::UNAUTHORIZED,
+                match cfg!(debug_assertions) {
+                    true => Cow::Owned(format!("Client not allowed: {client}")),
+                    false => Cow::Borrowed("Client not allowed"),
+                },
+            ),
             err @ AuthError::InvalidToken { reason: _ } => {
                 (StatusCode::BAD_REQUEST, Cow::Owned(err.to_string()))
             }
+            err @ AuthError::LayerNotInstalled => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Cow::Owned(err.to_string()),
+            ),
+            err @ AuthError::NotAuthenticated => {
+                (StatusCode::UNAUTHORIZED, Cow::Owned(err.to_string()))
+            }
+            err @ AuthError::MissingRoles => {
+                (StatusCode::UNAUTHORIZED, Cow::Owned(err.to_string()))
+            }
             AuthError::MissingExpectedRole { role } => (
                 StatusCode::UNAUTHORIZED,
                 match cfg!(debug_assertions) {
@@ -104,13 +732,52 @@ impl IntoResponse for AuthError {
                     false => Cow::Borrowed("Missing expected role"),
                 },
             ),
+            AuthError::MissingPermission { permission } => (
+                StatusCode::UNAUTHORIZED,
+                match cfg!(debug_assertions) {
+                    true => Cow::Owned(format!("Missing expected permission: {permission}")),
+                    false => Cow::Borrowed("Missing expected permission"),
+                },
+            ),
+            AuthError::UnmetRequirement { requirement } => (
+                StatusCode::UNAUTHORIZED,
+                match cfg!(debug_assertions) {
+                    true => Cow::Owned(format!("Unmet requirement: {requirement}")),
+                    false => Cow::Borrowed("Unmet requirement"),
+                },
+            ),
+            AuthError::MissingOrganization { organization } => (
+                StatusCode::UNAUTHORIZED,
+                match cfg!(debug_assertions) {
+                    true => Cow::Owned(format!("Missing expected organization: {organization}")),
+                    false => Cow::Borrowed("Missing expected organization"),
+                },
+            ),
+            AuthError::MissingOrganizationRole { organization, role } => (
+                StatusCode::UNAUTHORIZED,
+                match cfg!(debug_assertions) {
+                    true => Cow::Owned(format!(
+                        "Missing expected role {role} in organization {organization}"
+                    )),
+                    false => Cow::Borrowed("Missing expected organization role"),
+                },
+            ),
+            AuthError::ClaimRestrictionViolated { restriction } => (
+                StatusCode::UNAUTHORIZED,
+                match cfg!(debug_assertions) {
+                    true => Cow::Owned(format!("Claim restriction not met: {restriction}")),
+                    false => Cow::Borrowed("Claim restriction not met"),
+                },
+            ),
             err @ AuthError::UnexpectedRole => {
                 (StatusCode::UNAUTHORIZED, Cow::Owned(err.to_string()))
             }
-        };
-        let body = Json(json!({
-            "error": error_message,
-        }));
-        (status, body).into_response()
+        }
+    }
+}
+
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response {
+        self.into_response_with::<DefaultErrorBody>()
     }
 }
@@ -0,0 +1,65 @@
+use std::{fmt::Debug, time::Duration};
+
+use crate::error::{AuthError, DecodeFailureCategory, Severity};
+
+/// Something worth knowing happened while authenticating a request.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum AuthEvent<'a> {
+    /// A request was successfully authenticated.
+    Authenticated {
+        /// Subject of the accepted token.
+        subject: &'a str,
+        /// Authorized party of the accepted token.
+        authorized_party: &'a str,
+    },
+    /// A request could not be authenticated.
+    Rejected {
+        /// Why authentication failed.
+        error: &'a AuthError,
+        /// Classification of the failure, if it stems from decoding or validating the token itself.
+        category: Option<DecodeFailureCategory>,
+        /// How suspicious the failure is, derived from its `category`.
+        severity: Option<Severity>,
+    },
+}
+
+/// Receives all `AuthEvent`s emitted by a `KeycloakAuthLayer`.
+/// Implement this to feed authentication outcomes into your audit log, metrics or alerting.
+///
+/// Events are delivered synchronously on the request path. Implementations should therefore return quickly.
+pub trait AuthEventSink: Debug + Send + Sync {
+    fn on_event(&self, event: &AuthEvent<'_>);
+}
+
+/// Records how far in the future an accepted or rejected token was issued (if the `metrics` feature is enabled),
+/// allowing operators to detect clock drift between Keycloak and their fleet.
+pub(crate) fn record_issued_in_future(skew: Duration) {
+    #[cfg(feature = "metrics")]
+    metrics::histogram!("keycloak_auth_issued_in_future_seconds").record(skew.as_secs_f64());
+    #[cfg(not(feature = "metrics"))]
+    let _ = skew;
+}
+
+/// Forwards an event to the given sink (if any) and records it as a metric (if the `metrics` feature is enabled).
+pub(crate) fn emit(sink: Option<&dyn AuthEventSink>, event: AuthEvent<'_>) {
+    #[cfg(feature = "metrics")]
+    match &event {
+        AuthEvent::Authenticated { .. } => {
+            metrics::counter!("keycloak_auth_authenticated_total").increment(1);
+        }
+        AuthEvent::Rejected {
+            category, severity, ..
+        } => {
+            metrics::counter!(
+                "keycloak_auth_rejected_total",
+                "category" => category.map_or("none", |category| category.as_str()),
+                "severity" => severity.map_or("none", |severity| severity.as_str())
+            )
+            .increment(1);
+        }
+    }
+    if let Some(sink) = sink {
+        sink.on_event(&event);
+    }
+}
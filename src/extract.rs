@@ -0,0 +1,227 @@
+use std::marker::PhantomData;
+
+use axum::{async_trait, extract::FromRequestParts};
+use http::{request::Parts, Extensions};
+
+use crate::{decode::KeycloakToken, error::AuthError, role::Role, KeycloakAuthStatus};
+
+/// Names a role required by a `RequireRole` or `RequireRealmRole` guard.
+/// Implement this for a marker type, most easily using the `required_role!` macro.
+pub trait RequiredRole {
+    /// Name of the required role.
+    const ROLE: &'static str;
+}
+
+/// Declares a marker type implementing `RequiredRole`, usable in the `RequireRole` and `RequireRealmRole` guards.
+///
+/// ```
+/// use axum_keycloak_auth::{extract::RequireRole, required_role};
+///
+/// required_role!(pub Admin, "administrator");
+///
+/// pub async fn protected(_: RequireRole<Admin>) {}
+/// ```
+#[macro_export]
+macro_rules! required_role {
+    ($vis:vis $name:ident, $role:expr) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        $vis struct $name;
+
+        impl $crate::extract::RequiredRole for $name {
+            const ROLE: &'static str = $role;
+        }
+    };
+}
+
+/// Extractor guard only succeeding if the authenticated token carries the role named by `T`,
+/// either as a realm role or as a client role of any client.
+/// Use it as a handler parameter to reject requests before the handler body runs, making the requirement visible in the handler's signature.
+///
+/// Requires a `KeycloakAuthLayer<R>` to be installed on the route. Rejects with an `AuthError`.
+pub struct RequireRole<T: RequiredRole, R: Role = String>(PhantomData<fn() -> (T, R)>);
+
+/// Extractor guard only succeeding if the authenticated token carries the role named by `T` as a realm role.
+///
+/// Requires a `KeycloakAuthLayer<R>` to be installed on the route. Rejects with an `AuthError`.
+pub struct RequireRealmRole<T: RequiredRole, R: Role = String>(PhantomData<fn() -> (T, R)>);
+
+/// Finds the token stored by a `KeycloakAuthLayer<R>`, regardless of the layer's `PassthroughMode`.
+/// Fails with `AuthError::LayerNotInstalled` if no layer (for the role type `R`) processed the request,
+/// and with `AuthError::NotAuthenticated` if the layer let an unauthenticated request pass.
+fn token_from_parts<R: Role + 'static>(parts: &Parts) -> Result<&KeycloakToken<R>, AuthError> {
+    token_from_extensions(&parts.extensions)
+}
+
+/// Like `token_from_parts`, for services handling whole requests.
+pub(crate) fn token_from_extensions<R: Role + 'static>(
+    extensions: &Extensions,
+) -> Result<&KeycloakToken<R>, AuthError> {
+    if let Some(token) = extensions.get::<KeycloakToken<R>>() {
+        return Ok(token);
+    }
+    match extensions.get::<KeycloakAuthStatus<R>>() {
+        Some(KeycloakAuthStatus::Success(token)) => Ok(token),
+        Some(KeycloakAuthStatus::Failure(_)) => Err(AuthError::NotAuthenticated),
+        None => Err(AuthError::LayerNotInstalled),
+    }
+}
+
+/// Extracts the authenticated token, in both `PassthroughMode`s.
+/// Rejects with `AuthError::NotAuthenticated` if authentication failed in `PassthroughMode::Pass`.
+#[async_trait]
+impl<S, R> FromRequestParts<S> for KeycloakToken<R>
+where
+    S: Send + Sync,
+    R: Role + 'static,
+{
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        token_from_parts::<R>(parts).cloned()
+    }
+}
+
+#[async_trait]
+impl<S, T, R> FromRequestParts<S> for RequireRole<T, R>
+where
+    S: Send + Sync,
+    T: RequiredRole,
+    R: Role + 'static,
+{
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let token = token_from_parts::<R>(parts)?;
+        match token.has_any_role(&[T::ROLE.to_owned()]) {
+            true => Ok(Self(PhantomData)),
+            false => Err(AuthError::MissingExpectedRole {
+                role: T::ROLE.to_owned(),
+            }),
+        }
+    }
+}
+
+#[async_trait]
+impl<S, T, R> FromRequestParts<S> for RequireRealmRole<T, R>
+where
+    S: Send + Sync,
+    T: RequiredRole,
+    R: Role + 'static,
+{
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let token = token_from_parts::<R>(parts)?;
+        let required = R::from(T::ROLE.to_owned());
+        match token.realm_roles().any(|role| *role == required) {
+            true => Ok(Self(PhantomData)),
+            false => Err(AuthError::MissingExpectedRole {
+                role: T::ROLE.to_owned(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use axum::{body::Body, http::StatusCode, routing::get, Router};
+    use http::Request;
+    use tower::ServiceExt;
+
+    use crate::{
+        decode::KeycloakToken,
+        error::AuthError,
+        service::test::{claims, create_token, test_layer},
+        KeycloakAuthStatus, PassthroughMode,
+    };
+
+    use super::{token_from_parts, RequireRealmRole, RequireRole};
+
+    required_role!(Admin, "administrator");
+    required_role!(ManageAccount, "manage-account");
+
+    async fn status_of(router: Router, token: &str) -> StatusCode {
+        router
+            .oneshot(
+                Request::builder()
+                    .uri("/")
+                    .header("Authorization", format!("Bearer {token}"))
+                    .body(Body::empty())
+                    .expect("valid request"),
+            )
+            .await
+            .expect("infallible")
+            .status()
+    }
+
+    #[tokio::test]
+    async fn guards_reject_before_handler() {
+        let layer = test_layer!()
+            .passthrough_mode(PassthroughMode::Pass)
+            .build();
+        let token = create_token(claims());
+
+        let router = Router::new()
+            .route("/", get(|_: RequireRole<Admin>| async {}))
+            .layer(layer.clone());
+        assert_eq!(status_of(router, &token).await, StatusCode::OK);
+
+        let router = Router::new()
+            .route("/", get(|_: RequireRole<ManageAccount>| async {}))
+            .layer(layer.clone());
+        assert_eq!(status_of(router, &token).await, StatusCode::OK);
+
+        let router = Router::new()
+            .route("/", get(|_: RequireRealmRole<ManageAccount>| async {}))
+            .layer(layer.clone());
+        assert_eq!(status_of(router, &token).await, StatusCode::UNAUTHORIZED);
+
+        let router = Router::new()
+            .route("/", get(|_: RequireRealmRole<Admin>| async {}))
+            .layer(layer);
+        assert_eq!(status_of(router, "invalid").await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn tells_missing_layer_from_missing_authentication() {
+        let (mut parts, _) = Request::new(()).into_parts();
+        assert!(matches!(
+            token_from_parts::<String>(&parts),
+            Err(AuthError::LayerNotInstalled)
+        ));
+
+        parts
+            .extensions
+            .insert(KeycloakAuthStatus::<String>::Failure(Arc::new(
+                AuthError::MissingAuthorizationHeader,
+            )));
+        assert!(matches!(
+            token_from_parts::<String>(&parts),
+            Err(AuthError::NotAuthenticated)
+        ));
+    }
+
+    #[tokio::test]
+    async fn extracts_token() {
+        let layer = test_layer!()
+            .passthrough_mode(PassthroughMode::Pass)
+            .build();
+
+        let router = Router::new()
+            .route("/", get(|_: KeycloakToken<String>| async {}))
+            .layer(layer);
+        assert_eq!(
+            status_of(router.clone(), &create_token(claims())).await,
+            StatusCode::OK
+        );
+        assert_eq!(status_of(router, "invalid").await, StatusCode::UNAUTHORIZED);
+
+        let router = Router::new().route("/", get(|_: KeycloakToken<String>| async {}));
+        assert_eq!(
+            status_of(router, &create_token(claims())).await,
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+}
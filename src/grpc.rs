@@ -0,0 +1,93 @@
+use tonic::{metadata::MetadataValue, Code, Status};
+
+use crate::error::AuthError;
+
+/// Name of the metadata entry carrying the `AuthError::code` of a failed call.
+pub const ERROR_CODE_METADATA: &str = "x-auth-error-code";
+
+/// Maps errors onto gRPC status codes consistently with the HTTP status of `AuthError::into_response`:
+/// Errors of the request or its token become `Unauthenticated`, unmet authorization requirements (roles, permissions,
+/// organizations, clients, source IPs, claim restrictions) become `PermissionDenied`, temporary unavailability becomes `Unavailable`
+/// and any other server-side failure becomes `Internal`.
+/// The machine-readable `AuthError::code` is sent in the `ERROR_CODE_METADATA` entry. Like error responses,
+/// the message only contains sensitive details (e.g. the missing role) in debug builds.
+impl From<AuthError> for Status {
+    fn from(err: AuthError) -> Self {
+        let (status, message) = err.status_and_message();
+        let code = match &err {
+            AuthError::MissingRoles
+            | AuthError::SourceIpNotAllowed
+            | AuthError::ClientNotAllowed { client: _ }
+            | AuthError::MissingExpectedRole { role: _ }
+            | AuthError::MissingPermission { permission: _ }
+            | AuthError::UnmetRequirement { requirement: _ }
+            | AuthError::MissingOrganization { organization: _ }
+            | AuthError::MissingOrganizationRole {
+                organization: _,
+                role: _,
+            }
+            | AuthError::ClaimRestrictionViolated { restriction: _ }
+            | AuthError::UnexpectedRole => Code::PermissionDenied,
+            _ if status == http::StatusCode::SERVICE_UNAVAILABLE => Code::Unavailable,
+            _ if status.is_server_error() => Code::Internal,
+            _ => Code::Unauthenticated,
+        };
+        let mut status = Status::new(code, message);
+        status
+            .metadata_mut()
+            .insert(ERROR_CODE_METADATA, MetadataValue::from_static(err.code()));
+        status
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use tonic::{Code, Status};
+
+    use crate::error::AuthError;
+
+    use super::ERROR_CODE_METADATA;
+
+    #[test]
+    fn maps_errors_to_grpc_status_codes() {
+        for (err, code, error_code) in [
+            (
+                AuthError::TokenExpired,
+                Code::Unauthenticated,
+                "token_expired",
+            ),
+            (
+                AuthError::MissingAuthorizationHeader,
+                Code::Unauthenticated,
+                "missing_authorization_header",
+            ),
+            (
+                AuthError::MissingExpectedRole {
+                    role: String::from("administrator"),
+                },
+                Code::PermissionDenied,
+                "missing_expected_role",
+            ),
+            (AuthError::NoDecodingKey, Code::Internal, "no_decoding_key"),
+            (
+                AuthError::UpstreamUnavailable {
+                    retry_after: Duration::from_secs(30),
+                },
+                Code::Unavailable,
+                "upstream_unavailable",
+            ),
+        ] {
+            let status = Status::from(err);
+            assert_eq!(status.code(), code);
+            assert_eq!(
+                status
+                    .metadata()
+                    .get(ERROR_CODE_METADATA)
+                    .and_then(|value| value.to_str().ok()),
+                Some(error_code)
+            );
+        }
+    }
+}
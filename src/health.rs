@@ -0,0 +1,142 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+
+use crate::instance::KeycloakAuthInstance;
+
+/// The state of a `KeycloakAuthInstance`, as returned by `KeycloakAuthInstance::health`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InstanceHealth {
+    /// Whether the realm was discovered. Only `false` while a `StartupMode::Lazy` instance (or an instance started with cached keys,
+    /// see `KeycloakConfig::key_cache_dir`) could not discover the realm yet.
+    pub discovered: bool,
+    /// Number of keys currently known.
+    pub keys: usize,
+    /// Time since the keys were last fetched (or read) successfully, in whole seconds. `None` if they were never fetched.
+    #[serde(with = "crate::duration::option")]
+    pub keys_age: Option<Duration>,
+    /// Error of the most recent attempt to fetch the keys. `None` if it succeeded.
+    pub last_error: Option<String>,
+}
+
+impl InstanceHealth {
+    /// Whether tokens can be verified, i.e. whether any key is known, discovered or read from the `KeycloakConfig::key_cache_dir`.
+    /// Failed refreshes do not affect readiness, as the previously fetched keys are kept.
+    pub fn is_ready(&self) -> bool {
+        self.keys > 0
+    }
+}
+
+/// An axum handler reporting the `InstanceHealth` of the instance as JSON, with a 503 status unless it `is_ready`.
+/// Mount it as the readiness probe of a service, so that no traffic is routed to it before its keys are loaded.
+///
+/// ```
+/// use std::sync::Arc;
+/// use axum::{routing::get, Router};
+/// use axum_keycloak_auth::{health::readiness, instance::KeycloakAuthInstance};
+///
+/// fn probes(instance: Arc<KeycloakAuthInstance>) -> Router {
+///     Router::new()
+///         .route("/readyz", get(readiness))
+///         .with_state(instance)
+/// }
+/// ```
+pub async fn readiness(State(instance): State<Arc<KeycloakAuthInstance>>) -> Response {
+    let health = instance.health();
+    let status = match health.is_ready() {
+        true => StatusCode::OK,
+        false => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (status, Json(health)).into_response()
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use axum::{body::Body, http::StatusCode, routing::get, Router};
+    use http::Request;
+    use tower::ServiceExt;
+
+    use crate::{
+        instance::{test::serve_realm, KeycloakAuthInstance, KeycloakConfig, StartupMode},
+        retry::RetryPolicy,
+    };
+
+    use super::readiness;
+
+    async fn probe(
+        instance: std::sync::Arc<KeycloakAuthInstance>,
+    ) -> (StatusCode, serde_json::Value) {
+        let response = Router::new()
+            .route("/readyz", get(readiness))
+            .with_state(instance)
+            .oneshot(
+                Request::builder()
+                    .uri("/readyz")
+                    .body(Body::empty())
+                    .expect("valid request"),
+            )
+            .await
+            .expect("infallible");
+        let status = response.status();
+        let body = axum::body::HttpBody::data(&mut response.into_body())
+            .await
+            .expect("non-empty body")
+            .expect("readable body");
+        (status, serde_json::from_slice(&body).expect("JSON body"))
+    }
+
+    #[tokio::test]
+    async fn reports_readiness_of_instance() {
+        let unreachable = std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("free port");
+        let instance = KeycloakAuthInstance::new(
+            KeycloakConfig::builder()
+                .server(format!("http://{unreachable}"))
+                .realm("test")
+                .startup_mode(StartupMode::Lazy)
+                .min_key_refresh_interval(Duration::ZERO)
+                .retry_policy(RetryPolicy::none())
+                .build(),
+        )
+        .await
+        .expect("nothing fetched");
+        let health = instance.health();
+        assert!(!health.is_ready());
+        assert_eq!(health.keys_age, None);
+        assert_eq!(health.last_error, None);
+
+        assert!(instance.refresh().await.is_err());
+        let (status, body) = probe(instance).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["discovered"], false);
+        assert_eq!(body["keys"], 0);
+        assert!(body["last_error"]
+            .as_str()
+            .is_some_and(|error| error.contains(&unreachable.to_string())));
+
+        let realm = serve_realm(&["key-1"]).await;
+        let instance = KeycloakAuthInstance::new(
+            KeycloakConfig::builder()
+                .server(format!("http://{realm}"))
+                .realm("test")
+                .build(),
+        )
+        .await
+        .expect("realm discovered");
+        let (status, body) = probe(instance).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["discovered"], true);
+        assert_eq!(body["keys"], 1);
+        assert_eq!(body["keys_age"], "0s");
+        assert_eq!(body["last_error"], serde_json::Value::Null);
+    }
+}
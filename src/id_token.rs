@@ -0,0 +1,69 @@
+use std::ops::Deref;
+
+use axum::{async_trait, extract::FromRequestParts};
+use http::request::Parts;
+
+use crate::{decode::KeycloakToken, error::AuthError, role::Role};
+
+/// The ID token sent alongside the access token of a request, as some mobile SDKs do.
+///
+/// Stored as an extension by a `KeycloakAuthLayer` with an `id_token_header` configured, if the request carried an ID token.
+/// It was verified like the access token and was checked to be an ID token issued to the client the access token was issued to,
+/// for the same subject and user session. Extract an `Option<IdToken<R>>` in handlers accepting requests without an ID token.
+#[derive(Debug, Clone)]
+pub struct IdToken<R: Role>(pub KeycloakToken<R>);
+
+impl<R: Role> Deref for IdToken<R> {
+    type Target = KeycloakToken<R>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Checks that `id_token` belongs to the same login as `access_token`.
+pub(crate) fn cross_check<R: Role>(
+    id_token: &KeycloakToken<R>,
+    access_token: &KeycloakToken<R>,
+) -> Result<(), AuthError> {
+    let mismatch = |claim: &str| {
+        Err(AuthError::IdTokenMismatch {
+            claim: claim.to_owned(),
+        })
+    };
+    if !id_token
+        .audiences()
+        .iter()
+        .any(|audience| *audience == access_token.authorized_party)
+    {
+        return mismatch("aud");
+    }
+    if id_token.subject != access_token.subject {
+        return mismatch("sub");
+    }
+    // An ID token bound to a user session only belongs to access tokens of that very session.
+    if let Some(id_session) = id_token.session() {
+        if access_token.session() != Some(id_session) {
+            return mismatch("sid");
+        }
+    }
+    Ok(())
+}
+
+/// Extracts the ID token of the request. Rejects with `AuthError::MissingIdToken` if the request did not carry one.
+#[async_trait]
+impl<S, R> FromRequestParts<S> for IdToken<R>
+where
+    S: Send + Sync,
+    R: Role + 'static,
+{
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<IdToken<R>>()
+            .cloned()
+            .ok_or(AuthError::MissingIdToken)
+    }
+}
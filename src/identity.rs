@@ -0,0 +1,53 @@
+use std::future::Future;
+
+/// Identity of the authenticated caller, available to all code running as part of processing a request
+/// when the `KeycloakAuthLayer` was configured to `propagate_identity`.
+///
+/// This allows deeper layers (e.g. database query logging) to access the identity without threading the token through manually.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestIdentity {
+    /// Subject of the accepted token.
+    pub subject: String,
+    /// Authorized party of the accepted token.
+    pub authorized_party: String,
+}
+
+tokio::task_local! {
+    static REQUEST_IDENTITY: RequestIdentity;
+}
+
+impl RequestIdentity {
+    /// The identity of the request currently being processed on this task, if any.
+    ///
+    /// Note that this is not inherited by tasks spawned using `tokio::spawn`.
+    /// Use `RequestIdentity::scope` to carry the identity over into such tasks.
+    pub fn current() -> Option<RequestIdentity> {
+        REQUEST_IDENTITY.try_with(Clone::clone).ok()
+    }
+
+    /// Runs `future` with this identity being the `current` one.
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        REQUEST_IDENTITY.scope(self, future).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RequestIdentity;
+
+    #[tokio::test]
+    async fn identity_is_available_in_scope() {
+        let identity = RequestIdentity {
+            subject: String::from("sub"),
+            authorized_party: String::from("azp"),
+        };
+
+        assert_eq!(RequestIdentity::current(), None);
+        let current = identity
+            .clone()
+            .scope(async { RequestIdentity::current() })
+            .await;
+        assert_eq!(current, Some(identity));
+        assert_eq!(RequestIdentity::current(), None);
+    }
+}
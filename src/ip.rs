@@ -0,0 +1,382 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+};
+
+use axum::extract::ConnectInfo;
+use http::{Extensions, HeaderMap};
+
+use crate::{decode::RawClaims, error::AuthError};
+
+/// A range of IP addresses in CIDR notation, e.g. "10.0.0.0/8" or "2001:db8::/32".
+/// A plain address is treated as a range containing only that address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpRange {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpRange {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, canonical(ip)) {
+            (IpAddr::V4(range), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u32::from(range) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(range), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u128::from(range) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix_len) = match s.split_once('/') {
+            Some((addr, prefix_len)) => (addr, Some(prefix_len)),
+            None => (s, None),
+        };
+        let addr = canonical(
+            IpAddr::from_str(addr.trim())
+                .map_err(|err| format!("Invalid IP range '{s}': {err}"))?,
+        );
+        let max_prefix_len = match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        let prefix_len = match prefix_len {
+            Some(prefix_len) => prefix_len
+                .trim()
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix_len| *prefix_len <= max_prefix_len)
+                .ok_or_else(|| format!("Invalid prefix length in IP range '{s}'"))?,
+            None => max_prefix_len,
+        };
+        Ok(Self { addr, prefix_len })
+    }
+}
+
+/// Treats IPv4-mapped IPv6 addresses (e.g. "::ffff:10.0.0.1") as the IPv4 address they represent.
+fn canonical(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+        IpAddr::V4(_) => ip,
+    }
+}
+
+/// A header through which reverse proxies forward the address of the client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForwardedHeader {
+    /// The de-facto standard `X-Forwarded-For: client, proxy1, proxy2` header.
+    XForwardedFor,
+    /// The standardized `Forwarded: for=client, for=proxy1` header (RFC 7239).
+    Forwarded,
+}
+
+impl ForwardedHeader {
+    /// The addresses of all hops listed in the header, the client first. `None` for entries which are no IP address,
+    /// such as the obfuscated identifiers `Forwarded` allows. Returns `None` if the header is absent.
+    fn hops(&self, headers: &HeaderMap) -> Option<Vec<Option<IpAddr>>> {
+        let (name, parse): (_, fn(&str) -> Option<IpAddr>) = match self {
+            ForwardedHeader::XForwardedFor => ("x-forwarded-for", parse_node),
+            ForwardedHeader::Forwarded => ("forwarded", |element| {
+                element
+                    .split(';')
+                    .filter_map(|pair| pair.split_once('='))
+                    .find(|(key, _)| key.trim().eq_ignore_ascii_case("for"))
+                    .and_then(|(_, node)| parse_node(node.trim().trim_matches('"')))
+            }),
+        };
+        let values = headers.get_all(name);
+        let mut hops = Vec::new();
+        for value in values.iter() {
+            // A header which is no valid string cannot be trusted at all.
+            hops.extend(
+                value
+                    .to_str()
+                    .ok()?
+                    .split(',')
+                    .map(|element| parse(element.trim())),
+            );
+        }
+        match hops.is_empty() {
+            true => None,
+            false => Some(hops),
+        }
+    }
+}
+
+/// Parses "1.2.3.4", "1.2.3.4:80", "2001:db8::1" and "[2001:db8::1]:80".
+fn parse_node(node: &str) -> Option<IpAddr> {
+    IpAddr::from_str(node)
+        .ok()
+        .or_else(|| SocketAddr::from_str(node).ok().map(|addr| addr.ip()))
+        .or_else(|| IpAddr::from_str(node.strip_prefix('[')?.strip_suffix(']')?).ok())
+        .map(canonical)
+}
+
+/// Reverse proxies whose forwarding headers are trusted when determining the IP address of the client.
+/// Used by all features depending on the client's IP address.
+///
+/// The client IP is taken from axum's `ConnectInfo<SocketAddr>`, requiring the app to be served using `into_make_service_with_connect_info`.
+/// Only if that peer is a trusted proxy, the first present header of the configured `headers` is evaluated:
+/// Hops are processed from the nearest to the farthest, skipping trusted proxies. The first untrusted hop is the client.
+/// By default, no proxy is trusted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrustedProxies {
+    ranges: Vec<IpRange>,
+    trust_all: bool,
+    headers: Vec<ForwardedHeader>,
+}
+
+impl Default for TrustedProxies {
+    fn default() -> Self {
+        Self {
+            ranges: Vec::new(),
+            trust_all: false,
+            headers: vec![ForwardedHeader::XForwardedFor, ForwardedHeader::Forwarded],
+        }
+    }
+}
+
+impl TrustedProxies {
+    /// Trusts no proxy, always using the address of the peer.
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// Trusts proxies within the given ranges.
+    pub fn new(ranges: impl IntoIterator<Item = IpRange>) -> Self {
+        Self {
+            ranges: ranges.into_iter().collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Trusts every peer and hop, taking the client IP from the farthest hop of the forwarding header.
+    /// Only use this if the service is exclusively reachable through reverse proxies (re)setting the header!
+    /// Also works when the peer address is unknown.
+    pub fn all() -> Self {
+        Self {
+            trust_all: true,
+            ..Self::default()
+        }
+    }
+
+    /// The headers to evaluate, in order of preference. Defaults to `X-Forwarded-For`, then `Forwarded`.
+    pub fn headers(mut self, headers: impl IntoIterator<Item = ForwardedHeader>) -> Self {
+        self.headers = headers.into_iter().collect();
+        self
+    }
+
+    pub fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.trust_all || self.ranges.iter().any(|range| range.contains(ip))
+    }
+
+    /// Determines the IP address of the client. `None` if it cannot be determined reliably.
+    pub fn client_ip(&self, headers: &HeaderMap, extensions: &Extensions) -> Option<IpAddr> {
+        let peer = extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| canonical(addr.ip()));
+        let peer_is_trusted = match peer {
+            Some(peer) => self.is_trusted(peer),
+            None => self.trust_all,
+        };
+        if !peer_is_trusted {
+            return peer;
+        }
+        let Some(hops) = self.headers.iter().find_map(|header| header.hops(headers)) else {
+            return peer;
+        };
+        if !self.trust_all {
+            for hop in hops.iter().rev() {
+                match hop {
+                    Some(ip) if self.is_trusted(*ip) => continue,
+                    // The first untrusted hop, or an unparsable one which cannot be skipped safely.
+                    untrusted => return *untrusted,
+                }
+            }
+        }
+        // All hops are trusted. The farthest one is the client.
+        hops.first().copied().flatten()
+    }
+}
+
+/// Restricts the source IPs from which tokens are accepted, based on a claim of the token listing the allowed IP ranges.
+/// Some deployments embed such a claim in the tokens of machine clients.
+/// Tokens not carrying the claim are not restricted.
+/// The source IP is determined using the `trusted_proxies` of the `KeycloakAuthLayer`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IpAllowListClaim {
+    /// Name of the claim listing the allowed IP ranges in CIDR notation, either as an array of strings or a single space- or comma-separated string.
+    pub claim: String,
+}
+
+impl IpAllowListClaim {
+    pub fn new(claim: impl Into<String>) -> Self {
+        Self {
+            claim: claim.into(),
+        }
+    }
+
+    /// Fails if the token carries the claim but the source IP is unknown or not contained in any of the listed ranges.
+    pub(crate) fn check(
+        &self,
+        raw_claims: &RawClaims,
+        source_ip: Option<IpAddr>,
+    ) -> Result<(), AuthError> {
+        let ranges = match raw_claims.get(&self.claim) {
+            None | Some(serde_json::Value::Null) => return Ok(()),
+            Some(serde_json::Value::String(ranges)) => ranges
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|range| !range.is_empty())
+                .map(IpRange::from_str)
+                .collect::<Result<Vec<_>, _>>(),
+            Some(serde_json::Value::Array(ranges)) => ranges
+                .iter()
+                .map(|range| {
+                    range
+                        .as_str()
+                        .ok_or_else(|| String::from("IP ranges must be strings"))
+                        .and_then(IpRange::from_str)
+                })
+                .collect::<Result<Vec<_>, _>>(),
+            Some(_) => Err(format!("Claim '{}' has an unexpected format", self.claim)),
+        }
+        .map_err(|reason| AuthError::InvalidToken { reason })?;
+
+        match source_ip {
+            Some(source_ip) if ranges.iter().any(|range| range.contains(source_ip)) => Ok(()),
+            _ => Err(AuthError::SourceIpNotAllowed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        net::{IpAddr, SocketAddr},
+        str::FromStr,
+    };
+
+    use serde_json::json;
+
+    use crate::{decode::RawClaims, error::AuthError};
+
+    use axum::extract::ConnectInfo;
+    use http::{Extensions, HeaderMap, HeaderValue};
+
+    use super::{ForwardedHeader, IpAllowListClaim, IpRange, TrustedProxies};
+
+    fn ip(ip: &str) -> IpAddr {
+        IpAddr::from_str(ip).expect("valid IP")
+    }
+
+    #[test]
+    fn ip_range_contains() {
+        let range = IpRange::from_str("10.1.0.0/16").expect("valid range");
+        assert!(range.contains(ip("10.1.2.3")));
+        assert!(range.contains(ip("::ffff:10.1.2.3")));
+        assert!(!range.contains(ip("10.2.0.1")));
+
+        let single = IpRange::from_str("192.168.0.1").expect("valid range");
+        assert!(single.contains(ip("192.168.0.1")));
+        assert!(!single.contains(ip("192.168.0.2")));
+
+        let any = IpRange::from_str("0.0.0.0/0").expect("valid range");
+        assert!(any.contains(ip("1.2.3.4")));
+        assert!(!any.contains(ip("2001:db8::1")));
+
+        let v6 = IpRange::from_str("2001:db8::/32").expect("valid range");
+        assert!(v6.contains(ip("2001:db8:1::1")));
+        assert!(!v6.contains(ip("2001:db9::1")));
+
+        assert!(IpRange::from_str("10.0.0.0/33").is_err());
+        assert!(IpRange::from_str("nonsense").is_err());
+    }
+
+    #[test]
+    fn checks_source_ip_against_claim() {
+        let allow_list = IpAllowListClaim::new("allowed_ips");
+        let claims =
+            |value: serde_json::Value| RawClaims::from([(String::from("allowed_ips"), value)]);
+
+        assert!(allow_list.check(&RawClaims::new(), None).is_ok());
+        assert!(allow_list
+            .check(
+                &claims(json!(["10.0.0.0/8", "192.168.0.1"])),
+                Some(ip("10.9.9.9"))
+            )
+            .is_ok());
+        assert!(allow_list
+            .check(
+                &claims(json!("10.0.0.0/8, 192.168.0.1")),
+                Some(ip("192.168.0.1"))
+            )
+            .is_ok());
+        assert!(matches!(
+            allow_list.check(&claims(json!(["10.0.0.0/8"])), Some(ip("11.0.0.1"))),
+            Err(AuthError::SourceIpNotAllowed)
+        ));
+        assert!(matches!(
+            allow_list.check(&claims(json!(["10.0.0.0/8"])), None),
+            Err(AuthError::SourceIpNotAllowed)
+        ));
+        assert!(matches!(
+            allow_list.check(&claims(json!([42])), Some(ip("10.0.0.1"))),
+            Err(AuthError::InvalidToken { .. })
+        ));
+    }
+
+    #[test]
+    fn determines_client_ip_behind_trusted_proxies() {
+        let mut extensions = Extensions::new();
+        extensions.insert(ConnectInfo(SocketAddr::from(([10, 0, 0, 2], 4711))));
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("203.0.113.7, 198.51.100.1, 10.0.0.1"),
+        );
+        headers.insert(
+            "forwarded",
+            HeaderValue::from_static("for=\"[2001:db8::1]:80\";proto=https, for=10.0.0.1"),
+        );
+        let internal =
+            || TrustedProxies::new([IpRange::from_str("10.0.0.0/8").expect("valid range")]);
+
+        // Headers of untrusted peers are ignored.
+        assert_eq!(
+            TrustedProxies::none().client_ip(&headers, &extensions),
+            Some(ip("10.0.0.2"))
+        );
+        // The nearest untrusted hop is the client. Spoofed entries in front of it are ignored.
+        assert_eq!(
+            internal().client_ip(&headers, &extensions),
+            Some(ip("198.51.100.1"))
+        );
+        assert_eq!(
+            internal()
+                .headers([ForwardedHeader::Forwarded])
+                .client_ip(&headers, &extensions),
+            Some(ip("2001:db8::1"))
+        );
+        assert_eq!(
+            TrustedProxies::all().client_ip(&headers, &Extensions::new()),
+            Some(ip("203.0.113.7"))
+        );
+        assert_eq!(internal().client_ip(&headers, &Extensions::new()), None);
+        assert_eq!(
+            internal().client_ip(&HeaderMap::new(), &extensions),
+            Some(ip("10.0.0.2"))
+        );
+    }
+}
@@ -107,7 +107,7 @@
 //! You could for example create an enum containing all your known roles as variants with a special variant for unknown role names.
 //!
 //! ```rust
-//! #[derive(Debug, PartialEq, Eq, Clone)]
+//! #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 //! pub enum Role {
 //!     Administrator,
 //!     Unknown(String),
@@ -151,19 +151,83 @@
 //! - `PassthroughMode::Block`: Immediately return an error-response should authentication fail. This is the preferred mode and the default if omitted.
 //! - `PassthroughMode::Pass`: Always store a `KeycloakAuthStatus` containing the authentication result and defer the response generation to the handler or any deeper layers. You may want to use this mode i fine-grained error handling is required or you want to use additional layers which could still prove the user authenticated.
 //!
+//! ## Token types
+//!
+//! The `KeycloakAuthLayer` provides an `accepted_token_types` field, allowing you to choose which kinds of tokens are accepted:
+//!
+//! - `AcceptedTokenTypes::AccessOnly`: Only accept access tokens. This is the default if omitted.
+//! - `AcceptedTokenTypes::IdOnly`: Only accept ID tokens.
+//! - `AcceptedTokenTypes::Either`: Accept access and ID tokens. Use a separate router (and layer) for routes which should allow this.
+//!
+//! ## Key discovery
+//!
+//! Instead of providing a `DecodingKey`, a `KeycloakAuthInstance` can fetch the signing keys of a realm from Keycloak:
+//!
+//! ```rust,no_run
+//! use axum_keycloak_auth::{instance::{KeycloakAuthInstance, KeycloakConfig}, service::KeycloakAuthLayer};
+//!
+//! # async fn layer() -> Result<KeycloakAuthLayer<String>, axum_keycloak_auth::error::AuthError> {
+//! let instance = KeycloakAuthInstance::new(
+//!     KeycloakConfig::builder()
+//!         .server("https://keycloak.example.com")
+//!         .realm("my-realm")
+//!         .build(),
+//! )
+//! .await?;
+//! Ok(KeycloakAuthLayer::<String>::builder()
+//!     .instance(instance)
+//!     .expected_audiences(vec!["account"])
+//!     .build())
+//! # }
+//! ```
+//!
+//! ## Audiences
+//!
+//! By default, a token is accepted if it was issued for any of the `expected_audiences`.
+//! Set `audience_match` to `AudienceMatch::All` to require a token to be issued for all of them.
+//!
 
 #![forbid(unsafe_code)]
 //#![warn(missing_docs)]
 #![deny(clippy::unwrap_used)]
 
+#[cfg(not(any(feature = "tls-rustls", feature = "tls-native")))]
+compile_error!("Enable a TLS backend for requests to Keycloak: either the `tls-rustls` or the `tls-native` feature.");
+
 use std::sync::Arc;
 
 use role::Role;
 
+pub mod access_log;
+pub mod alias;
+pub mod audience;
+pub mod authorize;
+pub mod breaker;
 pub mod decode;
+pub mod duration;
 pub mod error;
+pub mod event;
+pub mod extract;
+#[cfg(feature = "tonic")]
+pub mod grpc;
+pub mod health;
+pub mod id_token;
+pub mod identity;
+pub mod instance;
+pub mod ip;
+pub mod limit;
+pub mod organization;
+pub mod permission;
+pub mod replay;
+pub mod restriction;
+pub mod retry;
 pub mod role;
+pub mod routing;
+pub mod schema;
 pub mod service;
+pub mod timings;
+pub mod usage;
+pub mod validator;
 
 /// The mode in which the authentication middleware may operate in.
 ///
@@ -178,10 +242,51 @@ pub enum PassthroughMode {
     Pass,
 }
 
+/// The kinds of tokens a `KeycloakAuthLayer` accepts, as determined by the JWT 'typ' claim.
+///
+/// APIs typically expect access tokens. Some clients (e.g. internal dashboards) send their ID token instead,
+/// which can be allowed for the routes serving them.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum AcceptedTokenTypes {
+    /// Only accept access tokens ('typ' is "Bearer"). This is the default.
+    AccessOnly,
+    /// Only accept ID tokens ('typ' is "ID").
+    IdOnly,
+    /// Accept access as well as ID tokens.
+    Either,
+}
+
+/// How the audiences of a token are matched against the `expected_audiences` of a `KeycloakAuthLayer`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum AudienceMatch {
+    /// The token must be issued for at least one of the expected audiences. This is the default.
+    Any,
+    /// The token must be issued for all of the expected audiences.
+    All,
+}
+
+/// What to do with tokens whose 'exp' or 'iat' claims lie beyond the range representable by `time::OffsetDateTime` (the years -9999 to 9999),
+/// as issued e.g. for "never expiring" service tokens.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TimestampRangePolicy {
+    /// Reject the token with `AuthError::TimestampOutOfRange`. This is the default.
+    Reject,
+    /// Clamp the timestamp to the earliest or latest representable point in time.
+    Clamp,
+}
+
+/// The expected format of the 'jti' (JWT ID) claim of tokens.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum JtiFormat {
+    /// Accept any JWT ID. This is the default.
+    Any,
+    /// Only accept UUIDs, as issued by Keycloak, rejecting other tokens with `AuthError::InvalidJti`.
+    /// The type prefix Keycloak (26+) adds to the JWT IDs of some tokens (e.g. "onrtac:") is accepted.
+    Uuid,
+}
+
 #[derive(Debug, Clone)]
-#[allow(clippy::large_enum_variant)]
 pub enum KeycloakAuthStatus<R: Role> {
-    // This variant is fairly large, but probably used most of the time. Leaving this non-boxed results in one less allocation each request.
     Success(decode::KeycloakToken<R>),
     Failure(Arc<error::AuthError>),
 }
@@ -0,0 +1,201 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::error::AuthError;
+
+/// Limits the number of tokens validated concurrently, protecting the CPU during token floods.
+///
+/// Validations beyond `max_concurrent` are queued. Requests are rejected with `AuthError::Overloaded`
+/// (a 503 response with a `Retry-After` header) if the queue already holds `max_queued` validations,
+/// or if a queued validation could not start within the `queue_deadline`.
+///
+/// Also limits concurrent requests to Keycloak, when configured as `KeycloakConfig::request_limit`.
+///
+/// Clones share the same limit. Can be deserialized from configuration, e.g. `{ "max_concurrent": 64, "max_queued": 256, "queue_deadline": "250ms" }`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "ValidationLimitConfig", into = "ValidationLimitConfig")]
+pub struct ValidationLimit {
+    max_concurrent: usize,
+    max_queued: usize,
+    queue_deadline: Duration,
+    retry_after: Duration,
+    permits: Arc<Semaphore>,
+    queued: Arc<AtomicUsize>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ValidationLimitConfig {
+    max_concurrent: usize,
+    max_queued: usize,
+    #[serde(with = "crate::duration")]
+    queue_deadline: Duration,
+    #[serde(default, with = "crate::duration::option")]
+    retry_after: Option<Duration>,
+}
+
+impl From<ValidationLimitConfig> for ValidationLimit {
+    fn from(config: ValidationLimitConfig) -> Self {
+        let limit = Self::new(
+            config.max_concurrent,
+            config.max_queued,
+            config.queue_deadline,
+        );
+        match config.retry_after {
+            Some(retry_after) => limit.retry_after(retry_after),
+            None => limit,
+        }
+    }
+}
+
+impl From<ValidationLimit> for ValidationLimitConfig {
+    fn from(limit: ValidationLimit) -> Self {
+        Self {
+            max_concurrent: limit.max_concurrent,
+            max_queued: limit.max_queued,
+            queue_deadline: limit.queue_deadline,
+            retry_after: Some(limit.retry_after),
+        }
+    }
+}
+
+impl ValidationLimit {
+    /// Creates a limit advising rejected clients to retry after one second.
+    pub fn new(max_concurrent: usize, max_queued: usize, queue_deadline: Duration) -> Self {
+        Self {
+            max_concurrent,
+            max_queued,
+            queue_deadline,
+            retry_after: Duration::from_secs(1),
+            permits: Arc::new(Semaphore::new(max_concurrent)),
+            queued: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// The duration advertised in the `Retry-After` header of rejected requests. Rounded up to full seconds.
+    pub fn retry_after(mut self, retry_after: Duration) -> Self {
+        self.retry_after = retry_after;
+        self
+    }
+
+    pub fn max_concurrent(&self) -> usize {
+        self.max_concurrent
+    }
+
+    pub fn max_queued(&self) -> usize {
+        self.max_queued
+    }
+
+    /// The number of validations currently waiting to start.
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::Acquire)
+    }
+
+    /// The number of validations currently running.
+    pub fn in_progress(&self) -> usize {
+        self.max_concurrent - self.permits.available_permits()
+    }
+
+    /// Waits for a validation slot. The validation may run for as long as the returned permit is alive.
+    pub(crate) async fn acquire(&self) -> Result<SemaphorePermit<'_>, AuthError> {
+        if let Ok(permit) = self.permits.try_acquire() {
+            return Ok(permit);
+        }
+        let overloaded = || AuthError::Overloaded {
+            retry_after: self.retry_after,
+        };
+        let queued = QueuedValidation::enter(&self.queued);
+        if queued.position >= self.max_queued {
+            return Err(overloaded());
+        }
+        let permit = tokio::time::timeout(self.queue_deadline, self.permits.acquire()).await;
+        drop(queued);
+        match permit {
+            Ok(Ok(permit)) => Ok(permit),
+            // Timed out, or the semaphore was closed (which never happens).
+            Ok(Err(_)) | Err(_) => Err(overloaded()),
+        }
+    }
+}
+
+/// Tracks one queued validation for as long as it is alive, so that it leaves the queue even if the waiting future is dropped.
+struct QueuedValidation<'a> {
+    queued: &'a AtomicUsize,
+    /// Number of validations queued before this one at the time of entering.
+    position: usize,
+}
+
+impl<'a> QueuedValidation<'a> {
+    fn enter(queued: &'a AtomicUsize) -> Self {
+        let position = queued.fetch_add(1, Ordering::AcqRel);
+        Self { queued, position }
+    }
+}
+
+impl<'a> Drop for QueuedValidation<'a> {
+    fn drop(&mut self) {
+        self.queued.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::error::AuthError;
+
+    use super::ValidationLimit;
+
+    #[tokio::test]
+    async fn queues_until_deadline() {
+        let limit = ValidationLimit::new(1, 1, Duration::from_millis(20));
+
+        let permit = limit.acquire().await.expect("free slot");
+        assert_eq!(limit.in_progress(), 1);
+
+        // The queue holds a single validation, which times out while the slot is taken.
+        let (queued, overflowing) = tokio::join!(limit.acquire(), async {
+            tokio::task::yield_now().await;
+            limit.acquire().await
+        });
+        assert!(matches!(queued, Err(AuthError::Overloaded { .. })));
+        assert!(matches!(overflowing, Err(AuthError::Overloaded { .. })));
+        assert_eq!(limit.queued(), 0);
+
+        drop(permit);
+        assert!(limit.acquire().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn leaves_queue_when_waiting_validation_is_dropped() {
+        let limit = ValidationLimit::new(1, 1, Duration::from_secs(60));
+        let _permit = limit.acquire().await.expect("free slot");
+
+        // E.g. the client disconnected while its validation was queued.
+        let waiting = tokio::time::timeout(Duration::from_millis(20), limit.acquire()).await;
+        assert!(waiting.is_err());
+        assert_eq!(limit.queued(), 0);
+    }
+
+    #[test]
+    fn deserializes_from_config() {
+        let limit: ValidationLimit = serde_json::from_value(serde_json::json!({
+            "max_concurrent": 64,
+            "max_queued": 256,
+            "queue_deadline": "250ms",
+            "retry_after": "2s",
+        }))
+        .expect("valid config");
+        assert_eq!(limit.max_concurrent(), 64);
+        assert_eq!(limit.max_queued(), 256);
+        assert_eq!(limit.queue_deadline, Duration::from_millis(250));
+        assert_eq!(limit.retry_after, Duration::from_secs(2));
+    }
+}
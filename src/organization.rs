@@ -0,0 +1,112 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// An organization the user is a member of, as known since Keycloak 24 (organizations feature).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Organization {
+    /// Alias of the organization, unique within the realm.
+    pub alias: String,
+    /// ID of the organization. Only present if the organization mapper is configured to add it.
+    pub id: Option<String>,
+    /// Roles of the user within the organization, as listed under the "roles" key of the organization's details.
+    /// Only present if a mapper adds them, as Keycloak has no built-in organization roles.
+    pub roles: Vec<String>,
+    /// Attributes of the organization. Only present if the organization mapper is configured to add them.
+    pub attributes: BTreeMap<String, Vec<String>>,
+}
+
+impl Organization {
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|own| own == role)
+    }
+}
+
+/// The Keycloak 'organization' claim. Depending on the configuration of the organization mapper, it either lists
+/// the aliases of the user's organizations, or maps each alias to further details (ID, roles and attributes).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum OrganizationClaim {
+    Alias(String),
+    Aliases(Vec<String>),
+    Detailed(BTreeMap<String, serde_json::Value>),
+}
+
+impl OrganizationClaim {
+    pub fn into_organizations(self) -> Vec<Organization> {
+        let from_alias = |alias: String| Organization {
+            alias,
+            id: None,
+            roles: Vec::new(),
+            attributes: BTreeMap::new(),
+        };
+        match self {
+            OrganizationClaim::Alias(alias) => vec![from_alias(alias)],
+            OrganizationClaim::Aliases(aliases) => aliases.into_iter().map(from_alias).collect(),
+            OrganizationClaim::Detailed(organizations) => organizations
+                .into_iter()
+                .map(|(alias, details)| {
+                    let mut organization = from_alias(alias);
+                    if let serde_json::Value::Object(details) = details {
+                        for (key, value) in details {
+                            match (key.as_str(), value) {
+                                ("id", serde_json::Value::String(id)) => organization.id = Some(id),
+                                ("roles", serde_json::Value::Array(roles)) => {
+                                    organization.roles = strings(roles);
+                                }
+                                (_, serde_json::Value::String(value)) => {
+                                    organization.attributes.insert(key, vec![value]);
+                                }
+                                (_, serde_json::Value::Array(values)) => {
+                                    organization.attributes.insert(key, strings(values));
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                    organization
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The string elements of `values`.
+fn strings(values: Vec<serde_json::Value>) -> Vec<String> {
+    values
+        .into_iter()
+        .filter_map(|value| match value {
+            serde_json::Value::String(value) => Some(value),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::OrganizationClaim;
+
+    #[test]
+    fn parses_all_claim_formats() {
+        let aliases: OrganizationClaim =
+            serde_json::from_value(json!(["acme", "globex"])).expect("valid claim");
+        let organizations = aliases.into_organizations();
+        assert_eq!(organizations.len(), 2);
+        assert_eq!(organizations[0].alias, "acme");
+        assert_eq!(organizations[0].id, None);
+
+        let detailed: OrganizationClaim = serde_json::from_value(json!({
+            "acme": { "id": "42", "roles": ["admin"], "region": ["eu", "us"], "tier": "gold" }
+        }))
+        .expect("valid claim");
+        let organizations = detailed.into_organizations();
+        assert_eq!(organizations.len(), 1);
+        assert_eq!(organizations[0].id.as_deref(), Some("42"));
+        assert!(organizations[0].has_role("admin"));
+        assert!(!organizations[0].attributes.contains_key("roles"));
+        assert_eq!(organizations[0].attributes["region"], vec!["eu", "us"]);
+        assert_eq!(organizations[0].attributes["tier"], vec!["gold"]);
+    }
+}
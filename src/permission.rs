@@ -0,0 +1,108 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{Arc, OnceLock},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::role::{KeycloakRole, Role};
+
+/// Maps role names to fine-grained application permissions (e.g. "invoice:write"),
+/// decoupling route checks from the names of roles in Keycloak.
+///
+/// Deserializes from (and serializes to) a plain map of role names to lists of permissions,
+/// allowing the mapping to be loaded from a configuration file.
+/// Roles are identified by their `Display` representation. Realm and client roles of the same name grant the same permissions.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PermissionMap(HashMap<String, BTreeSet<String>>);
+
+impl PermissionMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lets `role` grant all given `permissions`, in addition to the permissions already granted by it.
+    pub fn grant(
+        mut self,
+        role: impl Into<String>,
+        permissions: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.0
+            .entry(role.into())
+            .or_default()
+            .extend(permissions.into_iter().map(Into::into));
+        self
+    }
+
+    /// All permissions granted by any of the given roles.
+    pub fn permissions_of<R: Role>(&self, roles: &[KeycloakRole<R>]) -> BTreeSet<String> {
+        roles
+            .iter()
+            .filter_map(|role| self.0.get(&role.role().to_string()))
+            .flatten()
+            .cloned()
+            .collect()
+    }
+}
+
+/// Permissions of a token, resolved from its roles when first accessed.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LazyPermissions {
+    map: Option<Arc<PermissionMap>>,
+    resolved: OnceLock<BTreeSet<String>>,
+}
+
+impl LazyPermissions {
+    pub(crate) fn new(map: Arc<PermissionMap>) -> Self {
+        Self {
+            map: Some(map),
+            resolved: OnceLock::new(),
+        }
+    }
+
+    pub(crate) fn get<R: Role>(&self, roles: &[KeycloakRole<R>]) -> &BTreeSet<String> {
+        self.resolved.get_or_init(|| match &self.map {
+            Some(map) => map.permissions_of(roles),
+            None => BTreeSet::new(),
+        })
+    }
+}
+
+impl PartialEq for LazyPermissions {
+    fn eq(&self, other: &Self) -> bool {
+        self.map == other.map
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::role::KeycloakRole;
+
+    use super::PermissionMap;
+
+    #[test]
+    fn resolves_permissions_of_roles() {
+        let map: PermissionMap = serde_json::from_str(
+            r#"{ "accountant": ["invoice:read", "invoice:write"], "auditor": ["invoice:read"] }"#,
+        )
+        .expect("valid permission map");
+        let map = map.grant("administrator", ["user:delete"]);
+
+        let roles = vec![
+            KeycloakRole::Realm {
+                role: String::from("auditor"),
+            },
+            KeycloakRole::Client {
+                client: String::from("billing"),
+                role: String::from("accountant"),
+            },
+        ];
+
+        assert_eq!(
+            map.permissions_of(&roles).into_iter().collect::<Vec<_>>(),
+            vec!["invoice:read", "invoice:write"]
+        );
+        assert!(map.permissions_of::<String>(&[]).is_empty());
+    }
+}
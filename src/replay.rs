@@ -0,0 +1,96 @@
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    sync::{Mutex, PoisonError},
+};
+
+use time::OffsetDateTime;
+
+/// Remembers the IDs ('jti' claim) of already accepted tokens, for as long as these tokens are valid.
+///
+/// Used to reject replayed tokens on routes which must only ever be called once per token,
+/// e.g. webhook receivers using short-lived tokens.
+/// Implement this trait to share seen IDs between multiple instances of your service, e.g. by using Redis.
+pub trait ReplayStore: Debug + Send + Sync {
+    /// Records `jwt_id` as seen until `expires_at`.
+    /// Returns `false` if the ID was already recorded and has not yet expired, meaning that the token is replayed.
+    fn check_and_record(&self, jwt_id: &str, expires_at: OffsetDateTime) -> bool;
+}
+
+/// A `ReplayStore` keeping all seen IDs in memory. Expired IDs are dropped periodically.
+#[derive(Debug, Default)]
+pub struct InMemoryReplayStore {
+    state: Mutex<InMemoryReplayStoreState>,
+}
+
+#[derive(Debug, Default)]
+struct InMemoryReplayStoreState {
+    seen: HashMap<String, OffsetDateTime>,
+    next_purge: Option<OffsetDateTime>,
+}
+
+impl InMemoryReplayStore {
+    const PURGE_INTERVAL: time::Duration = time::Duration::minutes(1);
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of currently remembered IDs, including IDs which expired since the last purge.
+    pub fn len(&self) -> usize {
+        self.state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .seen
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl ReplayStore for InMemoryReplayStore {
+    fn check_and_record(&self, jwt_id: &str, expires_at: OffsetDateTime) -> bool {
+        let now = OffsetDateTime::now_utc();
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if state
+            .next_purge
+            .map_or(true, |next_purge| now >= next_purge)
+        {
+            state.seen.retain(|_, expires_at| *expires_at > now);
+            state.next_purge = Some(now + Self::PURGE_INTERVAL);
+        }
+
+        match state.seen.get(jwt_id) {
+            Some(seen_expires_at) if *seen_expires_at > now => false,
+            _ => {
+                state.seen.insert(jwt_id.to_owned(), expires_at);
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use time::{Duration, OffsetDateTime};
+
+    use super::{InMemoryReplayStore, ReplayStore};
+
+    #[test]
+    fn detects_replays_until_expiry() {
+        let store = InMemoryReplayStore::new();
+        let now = OffsetDateTime::now_utc();
+
+        assert!(store.check_and_record("a", now + Duration::minutes(5)));
+        assert!(!store.check_and_record("a", now + Duration::minutes(5)));
+        assert!(store.check_and_record("b", now + Duration::minutes(5)));
+
+        // An expired entry no longer blocks its ID.
+        assert!(store.check_and_record("c", now - Duration::seconds(1)));
+        assert!(store.check_and_record("c", now + Duration::minutes(5)));
+        assert_eq!(store.len(), 3);
+    }
+}
@@ -0,0 +1,167 @@
+use std::{fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{decode::RawClaims, error::AuthError};
+
+/// A restriction on a single claim of the token, covering simple attribute-based access control without a custom policy,
+/// e.g. requiring the 'hd' (hosted domain) claim to be "example.com" or the 'department' claim to be one of a few departments.
+///
+/// ```rust
+/// use axum_keycloak_auth::restriction::ClaimRestriction;
+///
+/// let restriction = ClaimRestriction::one_of("department", ["sales", "support"]);
+/// assert_eq!(restriction.to_string(), r#"department in ["sales","support"]"#);
+/// ```
+///
+/// Restrictions are (de)serializable, e.g. from `{ "claim": "hd", "equals": "example.com" }`
+/// or `{ "claim": "department", "one_of": ["sales", "support"] }`, so that they can be read from configuration files.
+/// For environment variables, they also parse from the short form "hd=example.com" or "department=sales|support",
+/// which only compares string values.
+/// A claim holding an array satisfies the restriction if any of its elements does. Absent claims never do.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClaimRestriction {
+    /// Name of the (top-level) claim.
+    pub claim: String,
+    #[serde(flatten)]
+    pub condition: ClaimCondition,
+}
+
+/// The values a claim restricted by a `ClaimRestriction` may take.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaimCondition {
+    /// The claim must equal this value.
+    Equals(serde_json::Value),
+    /// The claim must equal one of these values.
+    OneOf(Vec<serde_json::Value>),
+}
+
+impl ClaimRestriction {
+    /// Requires the `claim` to equal the `value`.
+    pub fn equals(claim: impl Into<String>, value: impl Into<serde_json::Value>) -> Self {
+        Self {
+            claim: claim.into(),
+            condition: ClaimCondition::Equals(value.into()),
+        }
+    }
+
+    /// Requires the `claim` to equal one of the `values`.
+    pub fn one_of(
+        claim: impl Into<String>,
+        values: impl IntoIterator<Item = impl Into<serde_json::Value>>,
+    ) -> Self {
+        Self {
+            claim: claim.into(),
+            condition: ClaimCondition::OneOf(values.into_iter().map(Into::into).collect()),
+        }
+    }
+
+    /// Fails with `AuthError::ClaimRestrictionViolated` if the claims do not satisfy this restriction.
+    pub fn check(&self, raw_claims: &RawClaims) -> Result<(), AuthError> {
+        let allowed = |value: &serde_json::Value| match &self.condition {
+            ClaimCondition::Equals(expected) => value == expected,
+            ClaimCondition::OneOf(expected) => expected.contains(value),
+        };
+        let satisfied = match raw_claims.get(&self.claim) {
+            Some(serde_json::Value::Array(values)) => values.iter().any(allowed),
+            Some(value) => allowed(value),
+            None => false,
+        };
+        match satisfied {
+            true => Ok(()),
+            false => Err(AuthError::ClaimRestrictionViolated {
+                restriction: self.to_string(),
+            }),
+        }
+    }
+}
+
+impl Display for ClaimRestriction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.condition {
+            ClaimCondition::Equals(value) => write!(f, "{} == {value}", self.claim),
+            ClaimCondition::OneOf(values) => {
+                write!(
+                    f,
+                    "{} in {}",
+                    self.claim,
+                    serde_json::Value::from(values.clone())
+                )
+            }
+        }
+    }
+}
+
+impl FromStr for ClaimRestriction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (claim, values) = s
+            .split_once('=')
+            .filter(|(claim, _)| !claim.trim().is_empty())
+            .ok_or_else(|| format!("Invalid claim restriction '{s}', expected 'claim=value'"))?;
+        let mut values = values.split('|').map(str::trim).collect::<Vec<_>>();
+        Ok(match values.len() {
+            1 => Self::equals(claim.trim(), values.remove(0)),
+            _ => Self::one_of(claim.trim(), values),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use crate::decode::RawClaims;
+
+    use super::ClaimRestriction;
+
+    #[test]
+    fn checks_plain_and_array_claims() {
+        let claims: RawClaims = serde_json::from_value(json!({
+            "hd": "example.com",
+            "department": ["engineering", "support"],
+        }))
+        .expect("valid claims");
+        let restrictions: Vec<ClaimRestriction> = serde_json::from_value(json!([
+            { "claim": "hd", "equals": "example.com" },
+            { "claim": "department", "one_of": ["sales", "support"] },
+        ]))
+        .expect("valid restrictions");
+        assert_eq!(
+            restrictions,
+            vec![
+                ClaimRestriction::equals("hd", "example.com"),
+                ClaimRestriction::one_of("department", ["sales", "support"]),
+            ]
+        );
+
+        for restriction in &restrictions {
+            assert!(restriction.check(&claims).is_ok());
+        }
+        assert!(ClaimRestriction::equals("hd", "example.org")
+            .check(&claims)
+            .is_err());
+        assert!(ClaimRestriction::one_of("department", ["sales"])
+            .check(&claims)
+            .is_err());
+        assert!(ClaimRestriction::equals("tenant", "acme")
+            .check(&claims)
+            .is_err());
+    }
+
+    #[test]
+    fn parses_short_form() {
+        assert_eq!(
+            "hd=example.com".parse::<ClaimRestriction>(),
+            Ok(ClaimRestriction::equals("hd", "example.com"))
+        );
+        assert_eq!(
+            "department = sales | support".parse::<ClaimRestriction>(),
+            Ok(ClaimRestriction::one_of("department", ["sales", "support"]))
+        );
+        assert!("example.com".parse::<ClaimRestriction>().is_err());
+        assert!("=example.com".parse::<ClaimRestriction>().is_err());
+    }
+}
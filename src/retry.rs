@@ -0,0 +1,104 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use typed_builder::TypedBuilder;
+
+/// How requests to Keycloak (fetching the discovery document and the realm's keys) are retried after transient failures,
+/// e.g. while Keycloak restarts alongside the application.
+///
+/// Only connection errors, timeouts and responses with a 5xx or 429 status are retried.
+/// Other failures (e.g. a 404 for an unknown realm) are returned immediately.
+///
+/// Can be deserialized from configuration, e.g. `{ "max_attempts": 5, "initial_backoff": "500ms", "max_backoff": "10s" }`.
+/// Absent fields take their defaults.
+#[derive(Debug, Clone, PartialEq, TypedBuilder, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first one. A value of 1 disables retries.
+    #[builder(default = 3)]
+    pub max_attempts: u32,
+
+    /// Delay before the first retry.
+    #[builder(default = Duration::from_millis(200))]
+    #[serde(with = "crate::duration")]
+    pub initial_backoff: Duration,
+
+    /// Factor by which the delay grows with every further retry.
+    #[builder(default = 2.0)]
+    pub multiplier: f64,
+
+    /// Upper bound of the delay between two attempts.
+    #[builder(default = Duration::from_secs(5))]
+    #[serde(with = "crate::duration")]
+    pub max_backoff: Duration,
+
+    /// Whether to randomize each delay to between half and all of its value,
+    /// so that a fleet of services started together does not retry in lockstep.
+    #[builder(default = true)]
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+impl RetryPolicy {
+    /// A policy never retrying failed requests.
+    pub fn none() -> Self {
+        Self::builder().max_attempts(1).build()
+    }
+
+    /// Delay before the given retry (starting at 1).
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let exponent = i32::try_from(retry.saturating_sub(1)).unwrap_or(i32::MAX);
+        let backoff = (self.initial_backoff.as_secs_f64() * self.multiplier.powi(exponent))
+            .min(self.max_backoff.as_secs_f64())
+            .max(0.0);
+        match self.jitter {
+            true => Duration::from_secs_f64(backoff * (0.5 + random_fraction() / 2.0)),
+            false => Duration::from_secs_f64(backoff),
+        }
+    }
+}
+
+/// A random number in [0, 1), good enough to spread out retries.
+fn random_fraction() -> f64 {
+    (RandomState::new().build_hasher().finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::RetryPolicy;
+
+    #[test]
+    fn backs_off_exponentially_up_to_max() {
+        let policy = RetryPolicy::builder()
+            .initial_backoff(Duration::from_millis(100))
+            .max_backoff(Duration::from_millis(500))
+            .jitter(false)
+            .build();
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(500));
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_millis(500));
+
+        let policy = RetryPolicy {
+            jitter: true,
+            ..policy
+        };
+        for retry in 1..10 {
+            let backoff = policy.backoff(retry);
+            assert!(backoff <= Duration::from_millis(500));
+            assert!(backoff >= Duration::from_millis(50));
+        }
+    }
+}
@@ -1,17 +1,24 @@
-use std::fmt::{Debug, Display};
+use std::{
+    collections::HashSet,
+    fmt::{Debug, Display},
+    hash::Hash,
+};
 
 use axum::response::IntoResponse;
 use serde::{Deserialize, Serialize};
 
 /// Describes any type that can act as a role.
-pub trait Role: Debug + Display + Clone + PartialEq + Eq + Send + Sync + From<String> {}
+pub trait Role:
+    Debug + Display + Clone + PartialEq + Eq + Hash + Send + Sync + From<String>
+{
+}
 
 /// Roles are read from JSON and are therefore always present as `String`s.
 /// Using `String` as the `Role` should be the default when not providing a custom `Role` type.
 impl Role for String {}
 
 /// A realm or client role.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum KeycloakRole<R: Role> {
     /// A realm role
@@ -28,6 +35,7 @@ pub enum KeycloakRole<R: Role> {
     },
 }
 
+/// Prefer these accessors over destructuring the enum, as its representation may change in the future.
 impl<R: Role> KeycloakRole<R> {
     pub fn role(&self) -> &R {
         match self {
@@ -35,19 +43,213 @@ impl<R: Role> KeycloakRole<R> {
             KeycloakRole::Client { client: _, role } => role,
         }
     }
+
+    /// The ID of the client this role belongs to, or `None` for realm roles.
+    pub fn client(&self) -> Option<&str> {
+        match self {
+            KeycloakRole::Realm { role: _ } => None,
+            KeycloakRole::Client { client, role: _ } => Some(client),
+        }
+    }
+
+    pub fn is_realm_role(&self) -> bool {
+        self.client().is_none()
+    }
+
+    /// Whether this is a role of the given client.
+    pub fn is_client_role_of(&self, client: &str) -> bool {
+        self.client() == Some(client)
+    }
+
+    /// Whether this is the `role` of the given `client`, or the realm role `role` if `client` is `None`.
+    pub fn matches(&self, client: Option<&str>, role: &R) -> bool {
+        self.client() == client && self.role() == role
+    }
+}
+
+/// The distinct roles of a token, indexed by name. Checking whether a role is present takes constant time,
+/// instead of scanning all roles, which matters for tokens carrying hundreds of roles checked many times per request.
+///
+/// Realm and client roles are not told apart.
+#[derive(Debug, Clone)]
+pub struct RoleSet<R: Role> {
+    roles: HashSet<R>,
+}
+
+impl<R: Role> RoleSet<R> {
+    pub fn new(roles: &[KeycloakRole<R>]) -> Self {
+        Self {
+            roles: roles.iter().map(|role| role.role().clone()).collect(),
+        }
+    }
+
+    pub fn contains(&self, role: &R) -> bool {
+        self.roles.contains(role)
+    }
+
+    /// The number of distinct roles.
+    pub fn len(&self) -> usize {
+        self.roles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.roles.is_empty()
+    }
+}
+
+/// A declarative role policy, serializable to be stored in configuration files (e.g. YAML or TOML routing tables) and loaded at startup.
+/// Check it using `KeycloakToken::satisfies` or `KeycloakToken::expect_requirement`, or set it as the `role_requirement` of a `KeycloakAuthLayer`.
+///
+/// Using the default (externally tagged) representation, the JSON `{ "any_of": [{ "role": "admin" }, { "client_role": { "client": "billing", "role": "viewer" } }] }`
+/// requires either the role "admin" or the "viewer" role of the "billing" client.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoleRequirement {
+    /// The role must be present, as a realm role or as a client role of any client.
+    Role(String),
+    /// The role must be present as a realm role.
+    RealmRole(String),
+    /// The role must be present as a role of the given client.
+    ClientRole { client: String, role: String },
+    /// Any role must match the glob pattern. See `glob_matches`.
+    Matching(String),
+    /// All of the requirements must be satisfied.
+    AllOf(Vec<RoleRequirement>),
+    /// At least one of the requirements must be satisfied.
+    AnyOf(Vec<RoleRequirement>),
+    /// The requirement must not be satisfied.
+    Not(Box<RoleRequirement>),
+}
+
+impl Display for RoleRequirement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let write_all =
+            |f: &mut std::fmt::Formatter<'_>, name: &str, requirements: &[RoleRequirement]| {
+                f.write_str(name)?;
+                f.write_str("(")?;
+                for (i, requirement) in requirements.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    Display::fmt(requirement, f)?;
+                }
+                f.write_str(")")
+            };
+        match self {
+            RoleRequirement::Role(role) => write!(f, "role({role})"),
+            RoleRequirement::RealmRole(role) => write!(f, "realm_role({role})"),
+            RoleRequirement::ClientRole { client, role } => {
+                write!(f, "client_role({client}, {role})")
+            }
+            RoleRequirement::Matching(pattern) => write!(f, "matching({pattern})"),
+            RoleRequirement::AllOf(requirements) => write_all(f, "all_of", requirements),
+            RoleRequirement::AnyOf(requirements) => write_all(f, "any_of", requirements),
+            RoleRequirement::Not(requirement) => write!(f, "not({requirement})"),
+        }
+    }
+}
+
+/// The roles added and removed between two tokens, e.g. before and after a token refresh.
+/// Realm and client roles are compared separately: Gaining a role for one client while losing it for another shows up as both.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoleDiff<R: Role> {
+    /// Roles only present in the newer token.
+    pub added: Vec<KeycloakRole<R>>,
+    /// Roles only present in the older token.
+    pub removed: Vec<KeycloakRole<R>>,
+}
+
+impl<R: Role> RoleDiff<R> {
+    pub fn new(before: &[KeycloakRole<R>], after: &[KeycloakRole<R>]) -> Self {
+        let missing_in = |roles: &[KeycloakRole<R>], candidates: &[KeycloakRole<R>]| {
+            let mut missing: Vec<KeycloakRole<R>> = Vec::new();
+            for candidate in candidates {
+                if !roles.contains(candidate) && !missing.contains(candidate) {
+                    missing.push(candidate.clone());
+                }
+            }
+            missing
+        };
+        Self {
+            added: missing_in(before, after),
+            removed: missing_in(after, before),
+        }
+    }
+
+    /// Whether both tokens carry the same roles.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// What to do with tokens carrying neither a 'realm_access' nor a 'resource_access' claim,
+/// as is common for service-account tokens and clients using a minimal set of scopes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MissingRolesPolicy<R: Role> {
+    /// Accept the token without any roles. This is the default.
+    AcceptEmpty,
+    /// Reject the token.
+    Reject,
+    /// Accept the token, assigning it this single realm role.
+    DefaultRole(R),
+}
+
+/// Checks whether `name` matches the glob `pattern`.
+/// A `*` in the pattern matches any (possibly empty) sequence of characters, a `?` matches exactly one character.
+/// All other characters must match exactly.
+pub fn glob_matches(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    let (mut p, mut n) = (0, 0);
+    // Position of the last '*' seen in the pattern and the position in name it currently covers up to.
+    let mut backtrack: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                // Let the last '*' swallow one more character and retry.
+                Some((star, covered)) => {
+                    backtrack = Some((star, covered + 1));
+                    p = star + 1;
+                    n = covered + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
 }
 
+/// Types holding roles which can tell how many roles they hold, without extracting them.
 pub trait NumRoles {
+    /// The number of roles held. Duplicates are counted, as a role is counted once for every realm or client it is assigned in.
+    /// This is exactly the number of `KeycloakRole`s `ExtractRoles::extract_roles` would produce.
     fn num_roles(&self) -> usize;
 }
 
+impl<T: NumRoles> NumRoles for &T {
+    fn num_roles(&self) -> usize {
+        (*self).num_roles()
+    }
+}
+
 impl<T: NumRoles> NumRoles for Option<T> {
     fn num_roles(&self) -> usize {
         self.as_ref().map(|it| it.num_roles()).unwrap_or(0)
     }
 }
 
+/// Types holding roles which can be turned into `KeycloakRole`s.
 pub trait ExtractRoles<R: Role> {
+    /// Appends all held roles to `target`, leaving existing elements untouched.
+    /// No guarantee is given about the order of the appended roles.
     fn extract_roles(self, target: &mut Vec<KeycloakRole<R>>);
 }
 
@@ -75,10 +277,16 @@ where
     }
 }
 
+/// Types which can be checked for the presence or absence of roles.
+/// A role is considered present if it is assigned as a realm role or as a client role of any client.
 pub trait ExpectRoles<R: Role> {
+    /// The error returned when a check fails. Can directly be returned from a handler.
     type Rejection: IntoResponse;
 
+    /// Fails on the first of the given roles which is not present.
     fn expect_roles<I: Into<R> + Clone>(&self, roles: &[I]) -> Result<(), Self::Rejection>;
+
+    /// Fails on the first of the given roles which is present.
     fn not_expect_roles<I: Into<R> + Clone>(&self, roles: &[I]) -> Result<(), Self::Rejection>;
 }
 
@@ -100,6 +308,38 @@ macro_rules! expect_role {
     };
 }
 
+#[macro_export]
+macro_rules! expect_role_matching {
+    ($token: expr, $pattern: expr) => {
+        if let Err(err) = $token.expect_role_matching($pattern) {
+            return axum::response::IntoResponse::into_response(err);
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! expect_permission {
+    ($token: expr, $permission: expr) => {
+        if let Err(err) = $token.expect_permission($permission) {
+            return axum::response::IntoResponse::into_response(err);
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! expect_organization {
+    ($token: expr, $alias: expr) => {
+        if let Err(err) = $token.expect_organization($alias) {
+            return axum::response::IntoResponse::into_response(err);
+        }
+    };
+    ($token: expr, $alias: expr, $role: expr) => {
+        if let Err(err) = $token.expect_organization_role($alias, $role) {
+            return axum::response::IntoResponse::into_response(err);
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! not_expect_roles {
     ($token: expr, $roles: expr) => {
@@ -118,3 +358,45 @@ macro_rules! not_expect_role {
         }
     };
 }
+
+#[cfg(test)]
+mod test {
+    use super::{glob_matches, KeycloakRole};
+
+    #[test]
+    fn accesses_roles_without_destructuring() {
+        let realm_role = KeycloakRole::Realm {
+            role: String::from("admin"),
+        };
+        let client_role = KeycloakRole::Client {
+            client: String::from("my-api"),
+            role: String::from("admin"),
+        };
+
+        assert_eq!(realm_role.client(), None);
+        assert_eq!(client_role.client(), Some("my-api"));
+        assert!(realm_role.is_realm_role());
+        assert!(!client_role.is_realm_role());
+        assert!(client_role.is_client_role_of("my-api"));
+        assert!(!client_role.is_client_role_of("other-api"));
+        assert!(realm_role.matches(None, &String::from("admin")));
+        assert!(!realm_role.matches(Some("my-api"), &String::from("admin")));
+        assert!(client_role.matches(Some("my-api"), &String::from("admin")));
+        assert!(!client_role.matches(Some("my-api"), &String::from("user")));
+    }
+
+    #[test]
+    fn glob_matching() {
+        assert!(glob_matches("admin", "admin"));
+        assert!(!glob_matches("admin", "administrator"));
+        assert!(glob_matches("tenant-*-admin", "tenant-42-admin"));
+        assert!(glob_matches("tenant-*-admin", "tenant--admin"));
+        assert!(glob_matches("tenant-*-admin", "tenant-a-admin-admin"));
+        assert!(!glob_matches("tenant-*-admin", "tenant-42-user"));
+        assert!(glob_matches("tenant-??", "tenant-42"));
+        assert!(!glob_matches("tenant-??", "tenant-4"));
+        assert!(glob_matches("*", ""));
+        assert!(glob_matches("**-admin", "x-admin"));
+        assert!(!glob_matches("", "admin"));
+    }
+}
@@ -0,0 +1,121 @@
+use std::{
+    collections::HashMap,
+    future::poll_fn,
+    task::{Context, Poll},
+};
+
+use axum::{body::Body, http::Request};
+use futures::future::BoxFuture;
+use tower::Service;
+
+use crate::decode::RawClaims;
+
+/// Routes requests to different inner services based on the value of a claim of the already validated token,
+/// e.g. to per-tenant service instances (by a 'tenant_id' claim) or per-realm instances (by the 'iss' claim) behind a single listener.
+///
+/// Must be wrapped by a `KeycloakAuthLayer` with `persist_raw_claims` enabled, as the claim is read from the `RawClaims` extension.
+/// Requests whose token lacks the claim, or has a value no route is registered for, are handled by the fallback service.
+/// String claims are matched by their value. Numbers and booleans are matched by their JSON representation.
+#[derive(Debug, Clone)]
+pub struct ClaimRouter<S> {
+    claim: String,
+    routes: HashMap<String, S>,
+    fallback: S,
+}
+
+impl<S> ClaimRouter<S> {
+    pub fn new(claim: impl Into<String>, fallback: S) -> Self {
+        Self {
+            claim: claim.into(),
+            routes: HashMap::new(),
+            fallback,
+        }
+    }
+
+    /// Handles requests whose token carries `value` in the routed claim with `service`.
+    pub fn route(mut self, value: impl Into<String>, service: S) -> Self {
+        self.routes.insert(value.into(), service);
+        self
+    }
+
+    fn service_for(&self, request: &Request<Body>) -> &S {
+        request
+            .extensions()
+            .get::<RawClaims>()
+            .and_then(|raw_claims| raw_claims.get(&self.claim))
+            .and_then(|value| match value {
+                serde_json::Value::String(value) => self.routes.get(value),
+                serde_json::Value::Number(_) | serde_json::Value::Bool(_) => {
+                    self.routes.get(&value.to_string())
+                }
+                _ => None,
+            })
+            .unwrap_or(&self.fallback)
+    }
+}
+
+impl<S> Service<Request<Body>> for ClaimRouter<S>
+where
+    S: Service<Request<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // Readiness of the chosen service is awaited per request, as the service is only known once the request is seen.
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        let mut service = self.service_for(&request).clone();
+        Box::pin(async move {
+            poll_fn(|cx| service.poll_ready(cx)).await?;
+            service.call(request).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::Infallible;
+
+    use axum::{body::Body, http::StatusCode, response::IntoResponse};
+    use http::Request;
+    use serde_json::json;
+    use tower::{Layer, ServiceExt};
+
+    use crate::service::test::{claims, create_token, test_layer};
+
+    use super::ClaimRouter;
+
+    #[tokio::test]
+    async fn routes_by_claim_value() {
+        let respond = |status: StatusCode| {
+            tower::service_fn(move |_request: Request<Body>| async move {
+                Ok::<_, Infallible>(status.into_response())
+            })
+        };
+        let router = ClaimRouter::new("tenant_id", respond(StatusCode::NOT_FOUND))
+            .route("acme", respond(StatusCode::OK))
+            .route("42", respond(StatusCode::ACCEPTED));
+        let service = test_layer!().persist_raw_claims(true).build().layer(router);
+
+        for (tenant_id, expected) in [
+            (json!("acme"), StatusCode::OK),
+            (json!(42), StatusCode::ACCEPTED),
+            (json!("other"), StatusCode::NOT_FOUND),
+            (json!(null), StatusCode::NOT_FOUND),
+        ] {
+            let mut claims = claims();
+            claims["tenant_id"] = tenant_id;
+            let request = Request::builder()
+                .header("Authorization", format!("Bearer {}", create_token(claims)))
+                .body(Body::empty())
+                .expect("valid request");
+            let response = service.clone().oneshot(request).await.expect("infallible");
+            assert_eq!(response.status(), expected);
+        }
+    }
+}
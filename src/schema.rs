@@ -0,0 +1,115 @@
+use std::fmt::Debug;
+
+use crate::{decode::RawClaims, error::AuthError};
+
+/// A JSON Schema the raw claims of every token must match, e.g. to require custom claims added by protocol mappers
+/// to have the expected shape. Catches regressions of a realm's mappers at the API boundary,
+/// instead of deep inside some handler.
+///
+/// The claims are validated as a single JSON object. Requires the `json-schema` feature.
+pub struct ClaimsSchema {
+    #[cfg(feature = "json-schema")]
+    schema: jsonschema::JSONSchema,
+    /// The schema as provided, kept for logging the configuration.
+    source: serde_json::Value,
+}
+
+impl ClaimsSchema {
+    /// Compiles `schema`, failing with `AuthError::InvalidClaimsSchema` if it is not a valid JSON Schema.
+    /// References to external schemas are not resolved.
+    #[cfg(feature = "json-schema")]
+    pub fn new(schema: serde_json::Value) -> Result<Self, AuthError> {
+        let compiled = jsonschema::JSONSchema::compile(&schema).map_err(|err| {
+            AuthError::InvalidClaimsSchema {
+                reason: err.to_string(),
+            }
+        })?;
+        Ok(Self {
+            schema: compiled,
+            source: schema,
+        })
+    }
+
+    /// The schema as provided.
+    pub fn source(&self) -> &serde_json::Value {
+        &self.source
+    }
+
+    /// Fails with `AuthError::ClaimsSchemaViolation`, describing the first violation, if `raw_claims` do not match the schema.
+    pub fn validate(&self, raw_claims: &RawClaims) -> Result<(), AuthError> {
+        #[cfg(feature = "json-schema")]
+        {
+            let claims = serde_json::Value::Object(
+                raw_claims
+                    .iter()
+                    .map(|(claim, value)| (claim.clone(), value.clone()))
+                    .collect(),
+            );
+            let violation = self.schema.validate(&claims).err().map(|mut errors| {
+                errors
+                    .next()
+                    .map(|err| match err.instance_path.to_string() {
+                        path if path.is_empty() => err.to_string(),
+                        path => format!("{err} (at {path})"),
+                    })
+                    .unwrap_or_default()
+            });
+            if let Some(reason) = violation {
+                return Err(AuthError::ClaimsSchemaViolation { reason });
+            }
+        }
+        #[cfg(not(feature = "json-schema"))]
+        let _ = raw_claims;
+        Ok(())
+    }
+}
+
+impl Debug for ClaimsSchema {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClaimsSchema")
+            .field("source", &self.source)
+            .finish()
+    }
+}
+
+#[cfg(all(test, feature = "json-schema"))]
+mod test {
+    use serde_json::json;
+
+    use crate::{decode::RawClaims, error::AuthError};
+
+    use super::ClaimsSchema;
+
+    #[test]
+    fn validates_claims_against_schema() {
+        let schema = ClaimsSchema::new(json!({
+            "type": "object",
+            "required": ["tenant"],
+            "properties": {
+                "tenant": { "type": "string", "minLength": 1 },
+                "clearance": { "type": "integer" },
+            },
+        }))
+        .expect("valid schema");
+
+        let claims = |value: serde_json::Value| -> RawClaims {
+            serde_json::from_value(value).expect("object")
+        };
+        assert!(schema
+            .validate(&claims(json!({ "tenant": "acme", "clearance": 3 })))
+            .is_ok());
+        assert!(matches!(
+            schema.validate(&claims(json!({ "clearance": 3 }))),
+            Err(AuthError::ClaimsSchemaViolation { reason }) if reason.contains("tenant")
+        ));
+        assert!(matches!(
+            schema.validate(&claims(json!({ "tenant": "acme", "clearance": "top" }))),
+            Err(AuthError::ClaimsSchemaViolation { reason }) if reason.contains("/clearance")
+        ));
+
+        assert!(matches!(
+            ClaimsSchema::new(json!({ "type": "no-such-type" })),
+            Err(AuthError::InvalidClaimsSchema { reason: _ })
+        ));
+    }
+}
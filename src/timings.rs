@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+/// How long the individual steps of authenticating a request took.
+///
+/// With the `timings` feature enabled, the `KeycloakAuthLayer` measures these steps for every successfully authenticated request
+/// and makes them available as a request extension, allowing to profile where authentication latency goes in production.
+/// Without the feature, nothing is measured.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ValidationTimings {
+    /// Extracting the token from the 'Authorization' header.
+    pub header_parse: Duration,
+    /// Waiting for a permit of the `validation_limit`, if configured.
+    pub queued: Duration,
+    /// Choosing the validator and key for the token.
+    pub key_lookup: Duration,
+    /// Verifying the token's signature and standard claims (including time spent on the blocking thread pool, if offloaded).
+    pub signature: Duration,
+    /// Parsing the claims into a `KeycloakToken`, including all checks of the parsed token other than role checks.
+    pub claims_parse: Duration,
+    /// Checking the `required_roles` and `role_requirement`.
+    pub roles: Duration,
+}
+
+impl ValidationTimings {
+    /// Time spent in all steps.
+    pub fn total(&self) -> Duration {
+        self.header_parse
+            + self.queued
+            + self.key_lookup
+            + self.signature
+            + self.claims_parse
+            + self.roles
+    }
+}
+
+/// Attributes the time passed since the previous lap to a step of `ValidationTimings`. Measures nothing without the `timings` feature.
+pub(crate) struct Stopwatch {
+    #[cfg(feature = "timings")]
+    last: std::time::Instant,
+    timings: ValidationTimings,
+}
+
+impl Stopwatch {
+    pub(crate) fn start() -> Self {
+        Self {
+            #[cfg(feature = "timings")]
+            last: std::time::Instant::now(),
+            timings: ValidationTimings::default(),
+        }
+    }
+
+    pub(crate) fn lap(&mut self, step: fn(&mut ValidationTimings) -> &mut Duration) {
+        #[cfg(feature = "timings")]
+        {
+            let now = std::time::Instant::now();
+            *step(&mut self.timings) += now - self.last;
+            self.last = now;
+        }
+        #[cfg(not(feature = "timings"))]
+        let _ = step;
+    }
+
+    pub(crate) fn timings(&self) -> ValidationTimings {
+        self.timings
+    }
+}
+
+#[cfg(all(test, feature = "timings"))]
+mod test {
+    use super::{Stopwatch, ValidationTimings};
+
+    #[test]
+    fn attributes_laps_to_steps() {
+        let mut stopwatch = Stopwatch::start();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        stopwatch.lap(|timings| &mut timings.signature);
+        stopwatch.lap(|timings| &mut timings.roles);
+
+        let timings = stopwatch.timings();
+        assert!(timings.signature >= std::time::Duration::from_millis(5));
+        assert!(timings.roles < timings.signature);
+        assert_eq!(timings.total(), timings.signature + timings.roles);
+        assert_eq!(
+            timings.header_parse,
+            ValidationTimings::default().header_parse
+        );
+    }
+}
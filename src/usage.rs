@@ -0,0 +1,127 @@
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+/// Receives every authenticated request, e.g. to report how much each client or user uses a service.
+///
+/// Called synchronously on the request path, so implementations should return quickly.
+/// Use `InMemoryUsageStats` to count requests in memory, or implement this trait to feed another store.
+pub trait UsageRecorder: Debug + Send + Sync {
+    /// Records a request authenticated with a token issued to `authorized_party` ('azp' claim) for `subject` ('sub' claim).
+    fn record(&self, authorized_party: &str, subject: &str);
+}
+
+/// Numbers of authenticated requests since the previous report, as produced by `InMemoryUsageStats`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UsageReport {
+    /// Requests per authorized party ('azp' claim), i.e. per client.
+    pub per_client: HashMap<String, u64>,
+    /// Requests per subject ('sub' claim), i.e. per user or service account.
+    pub per_subject: HashMap<String, u64>,
+}
+
+impl UsageReport {
+    /// Total number of requests.
+    pub fn total(&self) -> u64 {
+        self.per_client.values().sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.per_client.is_empty()
+    }
+}
+
+/// A `UsageRecorder` counting requests per client and per subject in memory.
+///
+/// Counts accumulate until they are taken, either by calling `take` or periodically using `flush_every`.
+/// As there is an entry for every subject seen since the last flush, flush regularly on services with many users.
+#[derive(Debug, Default)]
+pub struct InMemoryUsageStats {
+    report: Mutex<UsageReport>,
+}
+
+impl InMemoryUsageStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The counts since the last flush, without resetting them.
+    pub fn snapshot(&self) -> UsageReport {
+        self.report
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// The counts since the last flush, resetting them.
+    pub fn take(&self) -> UsageReport {
+        std::mem::take(&mut *self.report.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Passes the counts to `on_flush` every `interval` (resetting them), for as long as these stats are alive.
+    /// Must be called within a tokio runtime.
+    pub fn flush_every(
+        self: &Arc<Self>,
+        interval: Duration,
+        on_flush: impl Fn(UsageReport) + Send + 'static,
+    ) {
+        let stats = Arc::downgrade(self);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let Some(stats) = stats.upgrade() else {
+                    return;
+                };
+                on_flush(stats.take());
+            }
+        });
+    }
+}
+
+impl UsageRecorder for InMemoryUsageStats {
+    fn record(&self, authorized_party: &str, subject: &str) {
+        let mut report = self.report.lock().unwrap_or_else(PoisonError::into_inner);
+        *report
+            .per_client
+            .entry(authorized_party.to_owned())
+            .or_default() += 1;
+        *report.per_subject.entry(subject.to_owned()).or_default() += 1;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use super::{InMemoryUsageStats, UsageRecorder, UsageReport};
+
+    #[tokio::test]
+    async fn counts_requests_per_client_and_subject_until_flushed() {
+        let stats = Arc::new(InMemoryUsageStats::new());
+        stats.record("frontend", "alice");
+        stats.record("frontend", "bob");
+        stats.record("billing-service", "service-account-billing");
+
+        let report = stats.snapshot();
+        assert_eq!(report.total(), 3);
+        assert_eq!(report.per_client.get("frontend"), Some(&2));
+        assert_eq!(report.per_subject.get("alice"), Some(&1));
+
+        let flushed = Arc::new(Mutex::new(Vec::<UsageReport>::new()));
+        let reports = flushed.clone();
+        stats.flush_every(Duration::from_millis(50), move |report| {
+            reports.lock().expect("not poisoned").push(report);
+        });
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        let flushed = flushed.lock().expect("not poisoned");
+        assert_eq!(flushed.first(), Some(&report));
+        assert!(stats.snapshot().is_empty());
+    }
+}
@@ -0,0 +1,115 @@
+use std::{
+    fmt::{Debug, Display},
+    sync::Arc,
+};
+
+use jsonwebtoken::DecodingKey;
+
+use crate::{
+    audience::{expect_audiences, Audience},
+    decode::{RawClaims, RawToken, ValidationCache},
+    error::AuthError,
+};
+
+/// Verifies a raw JWT (e.g. checking its signature, lifetime and audience) and returns its claims.
+///
+/// The `KeycloakAuthLayer` uses a `KeycloakTokenValidator` by default.
+/// Additional validators can be registered per issuer, allowing tokens of other identity providers
+/// (e.g. when partially migrating away from Keycloak) to be accepted by the same layer.
+/// Claims returned by a validator must be parsable as `StandardClaims`,
+/// so validators for other identity providers may have to map their claims to Keycloak's format.
+pub trait TokenValidator: Debug + Send + Sync {
+    fn validate(&self, token: &str) -> Result<RawClaims, AuthError>;
+}
+
+/// Validates tokens issued by Keycloak, using a single `DecodingKey`.
+#[derive(Clone)]
+pub struct KeycloakTokenValidator {
+    decoding_key: Arc<DecodingKey>,
+    validations: Arc<ValidationCache>,
+}
+
+impl KeycloakTokenValidator {
+    pub fn new(
+        decoding_key: Arc<DecodingKey>,
+        expected_audiences: impl IntoIterator<Item = impl TryInto<Audience, Error = impl Display>>,
+    ) -> Self {
+        let expected_audiences = expect_audiences(expected_audiences);
+        Self {
+            decoding_key,
+            validations: Arc::new(ValidationCache::new(&expected_audiences)),
+        }
+    }
+
+    pub(crate) fn from_parts(
+        decoding_key: Arc<DecodingKey>,
+        validations: Arc<ValidationCache>,
+    ) -> Self {
+        Self {
+            decoding_key,
+            validations,
+        }
+    }
+
+    pub(crate) fn decoding_key(&self) -> &Arc<DecodingKey> {
+        &self.decoding_key
+    }
+}
+
+impl Debug for KeycloakTokenValidator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeycloakTokenValidator")
+            .field("validations", &self.validations)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TokenValidator for KeycloakTokenValidator {
+    fn validate(&self, token: &str) -> Result<RawClaims, AuthError> {
+        RawToken::try_from(token)?.decode(&self.decoding_key, &self.validations)
+    }
+}
+
+/// Accepts unsigned and self-signed tokens WITHOUT verifying their signature, as long as their claims match the `claims_template`.
+/// See `KeycloakAuthLayer::dangerous_dev_mode`. Only exists in debug builds.
+#[cfg(debug_assertions)]
+#[derive(Debug)]
+pub(crate) struct DevModeValidator {
+    pub(crate) claims_template: RawClaims,
+}
+
+#[cfg(debug_assertions)]
+impl TokenValidator for DevModeValidator {
+    fn validate(&self, token: &str) -> Result<RawClaims, AuthError> {
+        use base64::Engine;
+
+        let malformed = |reason: &str| AuthError::MalformedToken {
+            reason: reason.to_owned(),
+        };
+        let payload = token
+            .split('.')
+            .nth(1)
+            .ok_or_else(|| malformed("The token has no payload."))?;
+        let payload = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(payload)
+            .map_err(|err| malformed(&err.to_string()))?;
+        let raw_claims = serde_json::from_slice::<RawClaims>(&payload)
+            .map_err(|err| malformed(&err.to_string()))?;
+        if let Some((claim, _)) = self
+            .claims_template
+            .iter()
+            .find(|(claim, expected)| raw_claims.get(*claim) != Some(*expected))
+        {
+            return Err(AuthError::InvalidToken {
+                reason: format!(
+                    "Claim '{claim}' does not match the claims template of the development mode"
+                ),
+            });
+        }
+        tracing::warn!(
+            sub = ?raw_claims.get("sub"),
+            "Accepting a token WITHOUT verifying its signature, as the development mode is enabled"
+        );
+        Ok(raw_claims)
+    }
+}